@@ -0,0 +1,39 @@
+// only draws the shadows the plane receives, everything else stays transparent
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings as view_bindings,
+    mesh_view_types,
+    shadows,
+}
+
+struct ShadowCatcherMaterial {
+    // rgb color of the shadow, a its strength
+    shadow_color: vec4<f32>,
+    // xyz where the model touches the floor, w radius of the contact shadow
+    contact: vec4<f32>,
+    // strength of the contact shadow, 0.0 turns it off
+    ambient_occlusion: f32,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> material: ShadowCatcherMaterial;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let view_z = dot(vec4<f32>(
+        view_bindings::view.view_from_world[0].z,
+        view_bindings::view.view_from_world[1].z,
+        view_bindings::view.view_from_world[2].z,
+        view_bindings::view.view_from_world[3].z
+    ), in.world_position);
+    var lit = 1.0;
+    for (var i = 0u; i < view_bindings::lights.n_directional_lights; i = i + 1u) {
+        let flags = view_bindings::lights.directional_lights[i].flags;
+        if ((flags & mesh_view_types::DIRECTIONAL_LIGHT_FLAGS_SHADOWS_ENABLED_BIT) != 0u) {
+            lit = min(lit, shadows::fetch_directional_shadow(i, in.world_position, in.world_normal, view_z));
+        }
+    }
+    let distance = length(in.world_position.xz - material.contact.xz);
+    let contact = (1.0 - smoothstep(0.0, material.contact.w, distance)) * material.ambient_occlusion;
+    let shadow = max(1.0 - lit, contact);
+    return vec4<f32>(material.shadow_color.rgb, shadow * material.shadow_color.a);
+}
@@ -7,16 +7,39 @@ const SIMPLE_WALL: &str = "simpleWall.glb";
 
 pub const MAX_ASSET_ELEMENTS: usize = 2;
 
+// looping sound played at the model, see spatial_audio.rs
+pub struct ModelSound {
+    pub file_name: &'static str,
+    // name of a node in the gltf scene, None plays it at the model root
+    pub node: Option<&'static str>,
+    pub volume: f32,
+}
+
 pub struct AssetElementFile {
     pub file_name: &'static str,
+    // name of an environment preset, None uses the global one
+    pub environment: Option<&'static str>,
+    pub sounds: &'static [ModelSound],
+    // background music played in order, cross-faded when the scene changes, see mixer.rs
+    pub music: &'static [&'static str],
 }
 
 pub static ASSET_ELEMENTS: &[AssetElementFile] = &[
     AssetElementFile {
         file_name: SIMPLE_HUMAN_RIG,
+        environment: Some("studio"),
+        sounds: &[ModelSound {
+            file_name: "laser.wav",
+            node: Some("wrist_l"),
+            volume: 0.5,
+        }],
+        music: &["laser.ogg"],
     },
     AssetElementFile {
         file_name: SIMPLE_WALL,
+        environment: None,
+        sounds: &[],
+        music: &[],
     },
 ];
 
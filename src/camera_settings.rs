@@ -0,0 +1,105 @@
+use bevy::{camera::primitives::Aabb, prelude::*};
+use bevy_mod_xr::camera::XrProjection;
+
+use crate::settings::UserSettings;
+
+// near and far planes in meters, stored in the user settings
+#[derive(Resource)]
+pub struct CameraSettings {
+    // desktop perspective and orthographic cameras
+    pub near: f32,
+    pub far: f32,
+    // the xr projection has an infinite far plane
+    pub xr_near: f32,
+    // pulls the near plane closer when a model is right in front of the camera
+    pub auto_near: bool,
+    pub min_near: f32,
+}
+
+impl CameraSettings {
+    pub fn load(settings: &UserSettings) -> Self {
+        Self {
+            near: settings.get("camera.near").unwrap_or(0.003),
+            far: settings.get("camera.far").unwrap_or(1000.0),
+            xr_near: settings.get("camera.xr_near").unwrap_or(0.1),
+            auto_near: settings.get("camera.auto_near").unwrap_or(false),
+            min_near: settings.get("camera.min_near").unwrap_or(0.01),
+        }
+    }
+}
+
+pub fn setup_camera_settings(mut commands: Commands, settings: Res<UserSettings>) {
+    commands.insert_resource(CameraSettings::load(&settings));
+}
+
+// only writes the projection when a plane differs, so it isn't marked changed every frame
+pub fn apply_camera_settings(
+    settings: Res<CameraSettings>,
+    mut cameras: Query<(&mut Projection, &GlobalTransform), With<Camera3d>>,
+    models: Query<Entity, With<SceneRoot>>,
+    children: Query<&Children>,
+    bounds: Query<(&Aabb, &GlobalTransform, &ViewVisibility)>,
+) {
+    for (mut projection, camera) in &mut cameras {
+        let is_xr = matches!(&*projection, Projection::Custom(custom) if custom.get::<XrProjection>().is_some());
+        let base_near = if is_xr { settings.xr_near } else { settings.near };
+        let near = if settings.auto_near {
+            let distance = nearest_model_distance(camera.translation(), &models, &children, &bounds);
+            // half the distance keeps the model well behind the near plane,
+            // rounded to millimeters so it doesn't change with every tiny head movement
+            ((distance * 0.5 * 1000.0).round() / 1000.0).clamp(settings.min_near.min(base_near), base_near)
+        } else {
+            base_near
+        };
+        let current = match &*projection {
+            Projection::Perspective(perspective) => (perspective.near, perspective.far),
+            Projection::Orthographic(orthographic) => (orthographic.near, orthographic.far),
+            Projection::Custom(custom) => match custom.get::<XrProjection>() {
+                Some(xr) => (xr.near, settings.far),
+                None => {
+                    error_once!("unknown custom camera projection");
+                    continue;
+                }
+            },
+        };
+        if current == (near, settings.far) {
+            continue;
+        }
+        match &mut *projection {
+            Projection::Perspective(perspective) => {
+                perspective.near = near;
+                perspective.far = settings.far;
+            }
+            Projection::Orthographic(orthographic) => {
+                orthographic.near = near;
+                orthographic.far = settings.far;
+            }
+            Projection::Custom(custom) => {
+                if let Some(xr) = custom.get_mut::<XrProjection>() {
+                    xr.near = near;
+                }
+            }
+        }
+    }
+}
+
+// distance to the closest visible bounds of the shown models
+fn nearest_model_distance(
+    position: Vec3,
+    models: &Query<Entity, With<SceneRoot>>,
+    children: &Query<&Children>,
+    bounds: &Query<(&Aabb, &GlobalTransform, &ViewVisibility)>,
+) -> f32 {
+    models
+        .iter()
+        .flat_map(|model| children.iter_descendants(model))
+        .filter_map(|child| bounds.get(child).ok())
+        .filter(|(_, _, visibility)| visibility.get())
+        .map(|(aabb, global, _)| {
+            let local = global.affine().inverse().transform_point3(position);
+            let scale = global.scale().min_element().max(f32::EPSILON);
+            let outside = (Vec3A::from(local) - aabb.center).abs() - aabb.half_extents;
+            outside.max(Vec3A::ZERO).length() * scale
+        })
+        .fold(f32::INFINITY, f32::min)
+}
@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+use bevy_mod_openxr::{exts::OxrExtensions, init::xr_entry};
+use schminput::prelude::*;
+
+use crate::HP_MIXED_REALITY_PROFILE;
+
+// what the OpenXR runtime supports, probed once before the app is built
+// other systems check this instead of running into XR_ERROR_PATH_UNSUPPORTED
+// or a failing hand tracker creation (see pittfalls.txt)
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct XrCapabilities {
+    pub runtime_available: bool,
+    pub passthrough: bool,
+    pub hand_tracking: bool,
+    pub hp_controller: bool,
+}
+
+impl XrCapabilities {
+    // runs before the app (and its logger) exists, so failures are reported by log_capabilities
+    pub fn probe() -> Self {
+        let Ok(entry) = xr_entry() else {
+            return Self::default();
+        };
+        #[cfg(target_os = "android")]
+        if entry.initialize_android_loader().is_err() {
+            return Self::default();
+        }
+        let Ok(available) = entry.enumerate_extensions() else {
+            return Self::default();
+        };
+        Self {
+            runtime_available: true,
+            passthrough: available.fb_passthrough,
+            hand_tracking: available.ext_hand_tracking,
+            hp_controller: available.ext_hp_mixed_reality_controller,
+        }
+    }
+
+    // only request extensions the runtime reported as available
+    pub fn extensions(&self) -> OxrExtensions {
+        let mut exts = OxrExtensions::default();
+        if self.passthrough {
+            exts.enable_fb_passthrough();
+        }
+        exts.ext_hand_tracking = self.hand_tracking;
+        exts.ext_hp_mixed_reality_controller = self.hp_controller;
+        exts
+    }
+
+    // suggest the binding for the oculus touch profile and, if the extension is there,
+    // for the hp mixed reality controller
+    pub fn bindings(&self, path: &'static str) -> OxrBindings {
+        let bindings = OxrBindings::new().bindings(OCULUS_TOUCH_PROFILE, [path]);
+        if self.hp_controller {
+            bindings.bindings(HP_MIXED_REALITY_PROFILE, [path])
+        } else {
+            bindings
+        }
+    }
+}
+
+pub fn log_capabilities(capabilities: Res<XrCapabilities>) {
+    if !capabilities.runtime_available {
+        warn!("no OpenXR runtime found, all xr features disabled");
+        return;
+    }
+    info!("xr capabilities: {:?}", *capabilities);
+    if !capabilities.passthrough {
+        warn!("passthrough not supported by the runtime");
+    }
+    if !capabilities.hand_tracking {
+        warn!("hand tracking not supported by the runtime, hand trackers disabled");
+    }
+    if !capabilities.hp_controller {
+        info!("hp mixed reality controller not supported, skipping its bindings");
+    }
+}
@@ -0,0 +1,336 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    asset::RenderAssetUsages,
+    camera::{ManualTextureViewHandle, RenderTarget},
+    image::TextureFormatPixelInfo,
+    prelude::*,
+    render::{
+        render_resource::{Extent3d, TextureDimension},
+        view::screenshot::{save_to_disk, Screenshot, ScreenshotCaptured},
+    },
+};
+use bevy_mod_xr::camera::XrCamera;
+use schminput::prelude::*;
+
+use crate::{
+    settings::{data_dir, UserSettings},
+    tools_menu::{Tool, ToolInput},
+};
+
+const CAPTURES_DIR: &str = "captures";
+// an eye image arrives a few frames after it was requested, a pair still waiting
+// for its other eye after this long is dropped
+const STEREO_TIMEOUT: f32 = 2.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureView {
+    // both eyes side by side in one image
+    Combined,
+    // one image per eye
+    Eyes,
+    // the desktop window, also used when there is no headset
+    Desktop,
+}
+
+impl CaptureView {
+    pub fn name(self) -> &'static str {
+        match self {
+            CaptureView::Combined => "combined",
+            CaptureView::Eyes => "eyes",
+            CaptureView::Desktop => "desktop",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [CaptureView::Combined, CaptureView::Eyes, CaptureView::Desktop]
+            .into_iter()
+            .find(|view| view.name() == name)
+    }
+
+    fn next(self) -> Self {
+        match self {
+            CaptureView::Combined => CaptureView::Eyes,
+            CaptureView::Eyes => CaptureView::Desktop,
+            CaptureView::Desktop => CaptureView::Combined,
+        }
+    }
+}
+
+// a frame sequence written to its own folder
+pub struct Recording {
+    dir: PathBuf,
+    frame: u32,
+    // seconds until the next frame is captured
+    next_frame: f32,
+}
+
+#[derive(Resource)]
+pub struct Capture {
+    pub view: CaptureView,
+    pub recording: Option<Recording>,
+    // frames per second of a recording, every frame is a full screenshot so keep it low
+    record_fps: f32,
+    screenshot_action: Entity,
+    record_action: Entity,
+    view_action: Entity,
+}
+
+struct StereoPair {
+    eyes: [Option<Image>; 2],
+    // elapsed seconds when the screenshots were requested
+    requested: f32,
+}
+
+// eye images waiting for the other eye, by the path of the combined image
+#[derive(Resource, Default)]
+pub struct PendingStereo {
+    pairs: HashMap<PathBuf, StereoPair>,
+}
+
+pub fn setup_capture(mut commands: Commands, settings: Res<UserSettings>) {
+    let set = commands.spawn(ActionSet::new("capture", "Capture", 1)).id();
+    let screenshot_action = commands
+        .spawn((
+            Action::new("screenshot", "Screenshot", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::F12).just_pressed()),
+            GamepadBindings::new()
+                .bind(GamepadBinding::new(GamepadBindingSource::DPadRight).button_just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    let record_action = commands
+        .spawn((
+            Action::new("record", "Start Or Stop Recording", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::F11).just_pressed()),
+            GamepadBindings::new()
+                .bind(GamepadBinding::new(GamepadBindingSource::DPadLeft).button_just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    let view_action = commands
+        .spawn((
+            Action::new("cycle_capture_view", "Cycle Capture View", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::F10).just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    commands.insert_resource(Capture {
+        view: settings
+            .get::<String>("capture.view")
+            .and_then(|name| CaptureView::from_name(&name))
+            .unwrap_or(CaptureView::Combined),
+        recording: None,
+        record_fps: settings.get("capture.record_fps").unwrap_or(10.0),
+        screenshot_action,
+        record_action,
+        view_action,
+    });
+    commands.insert_resource(PendingStereo::default());
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+// keys or gamepad, in the headset the tools menu
+pub fn capture(
+    mut commands: Commands,
+    mut capture: ResMut<Capture>,
+    mut pending: ResMut<PendingStereo>,
+    bool_value: Query<&BoolActionValue>,
+    mut tool_input: MessageReader<ToolInput>,
+    eyes: Query<(&XrCamera, &RenderTarget)>,
+    time: Res<Time>,
+    mut settings: ResMut<UserSettings>,
+) {
+    let pressed = |action: Entity| bool_value.get(action).unwrap().any;
+    let mut screenshot = pressed(capture.screenshot_action);
+    let mut record = pressed(capture.record_action);
+    let mut cycle_view = pressed(capture.view_action);
+    for input in tool_input.read() {
+        match input.tool {
+            Tool::Screenshot => screenshot = true,
+            Tool::Recording => record = !record,
+            Tool::CaptureView => cycle_view = !cycle_view,
+            _ => {}
+        }
+    }
+    if cycle_view {
+        capture.view = capture.view.next();
+        info!("capture view: {}", capture.view.name());
+        settings.set("capture.view", capture.view.name());
+    }
+    let now = time.elapsed_secs();
+    pending.pairs.retain(|path, pair| {
+        let waiting = now - pair.requested < STEREO_TIMEOUT;
+        if !waiting {
+            warn!("an eye image is missing, {} is not saved", path.display());
+        }
+        waiting
+    });
+    let captures = data_dir().join(CAPTURES_DIR);
+    // the eye cameras render into the swapchain images
+    let mut eye_views: Vec<(u32, ManualTextureViewHandle)> = eyes
+        .iter()
+        .filter_map(|(eye, target)| match target {
+            RenderTarget::TextureView(handle) => Some((eye.0, *handle)),
+            _ => None,
+        })
+        .collect();
+    eye_views.sort_by_key(|(eye, _)| *eye);
+    let view = capture.view;
+
+    if screenshot {
+        let base = captures.join(format!("screenshot_{}", timestamp()));
+        if let Err(err) = fs::create_dir_all(&captures) {
+            warn!("unable to create {}: {}", captures.display(), err);
+        } else {
+            capture_frame(&mut commands, &mut pending, now, view, &eye_views, &base);
+        }
+    }
+
+    if record {
+        capture.recording = match capture.recording.take() {
+            Some(recording) => {
+                info!("recorded {} frames to {}", recording.frame, recording.dir.display());
+                None
+            }
+            None => {
+                let dir = captures.join(format!("recording_{}", timestamp()));
+                match fs::create_dir_all(&dir) {
+                    Ok(()) => {
+                        info!("recording to {}", dir.display());
+                        Some(Recording {
+                            dir,
+                            frame: 0,
+                            next_frame: 0.0,
+                        })
+                    }
+                    Err(err) => {
+                        warn!("unable to create {}: {}", dir.display(), err);
+                        None
+                    }
+                }
+            }
+        };
+    }
+
+    let record_fps = capture.record_fps;
+    let Some(recording) = &mut capture.recording else {
+        return;
+    };
+    recording.next_frame -= time.delta_secs();
+    if recording.next_frame > 0.0 {
+        return;
+    }
+    recording.next_frame += 1.0 / record_fps;
+    let base = recording.dir.join(format!("frame_{:05}", recording.frame));
+    recording.frame += 1;
+    capture_frame(&mut commands, &mut pending, now, view, &eye_views, &base);
+}
+
+fn capture_frame(
+    commands: &mut Commands,
+    pending: &mut PendingStereo,
+    now: f32,
+    view: CaptureView,
+    eye_views: &[(u32, ManualTextureViewHandle)],
+    base: &Path,
+) {
+    let with_suffix = |suffix: &str| {
+        let mut name = base.as_os_str().to_owned();
+        name.push(suffix);
+        PathBuf::from(name)
+    };
+    match view {
+        CaptureView::Combined if eye_views.len() == 2 => {
+            let path = with_suffix(".png");
+            pending.pairs.insert(
+                path.clone(),
+                StereoPair {
+                    eyes: [None, None],
+                    requested: now,
+                },
+            );
+            for (index, (_, handle)) in eye_views.iter().enumerate() {
+                let path = path.clone();
+                commands.spawn(Screenshot::texture_view(*handle)).observe(
+                    move |captured: On<ScreenshotCaptured>, mut pending: ResMut<PendingStereo>| {
+                        // dropped when the other eye took too long
+                        let Some(pair) = pending.pairs.get_mut(&path) else {
+                            return;
+                        };
+                        pair.eyes[index] = Some(captured.image.clone());
+                        let [Some(left), Some(right)] = &pair.eyes else {
+                            return;
+                        };
+                        save_side_by_side(left, right, &path);
+                        pending.pairs.remove(&path);
+                    },
+                );
+            }
+        }
+        CaptureView::Combined | CaptureView::Eyes if !eye_views.is_empty() => {
+            for (eye, handle) in eye_views {
+                let name = if *eye == 0 { "_left.png" } else { "_right.png" };
+                commands
+                    .spawn(Screenshot::texture_view(*handle))
+                    .observe(save_to_disk(with_suffix(name)));
+            }
+        }
+        _ => {
+            commands
+                .spawn(Screenshot::primary_window())
+                .observe(save_to_disk(with_suffix(".png")));
+        }
+    }
+}
+
+fn save_side_by_side(left: &Image, right: &Image, path: &Path) {
+    let format = left.texture_descriptor.format;
+    let size = left.size();
+    let (Some(left_data), Some(right_data), Ok(pixel_size)) =
+        (&left.data, &right.data, format.pixel_size())
+    else {
+        error!("unable to combine the eye images for {}", path.display());
+        return;
+    };
+    if right.size() != size || right.texture_descriptor.format != format {
+        error!("the eye images differ, unable to combine them for {}", path.display());
+        return;
+    }
+    let row = size.x as usize * pixel_size;
+    let mut data = Vec::with_capacity(left_data.len() * 2);
+    for (left_row, right_row) in left_data.chunks_exact(row).zip(right_data.chunks_exact(row)) {
+        data.extend_from_slice(left_row);
+        data.extend_from_slice(right_row);
+    }
+    let combined = Image::new(
+        Extent3d {
+            width: size.x * 2,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        format,
+        RenderAssetUsages::default(),
+    );
+    match combined.try_into_dynamic() {
+        // the alpha channel holds brightness values with hdr, like in save_to_disk
+        Ok(image) => match image.to_rgb8().save(path) {
+            Ok(()) => info!("screenshot saved to {}", path.display()),
+            Err(err) => error!("unable to save {}: {}", path.display(), err),
+        },
+        Err(err) => error!("unable to convert the screenshot {}: {}", path.display(), err),
+    }
+}
@@ -0,0 +1,197 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use bevy_mod_openxr::{resources::OxrInstance, session::OxrSession};
+use schminput::prelude::OCULUS_TOUCH_PROFILE;
+
+use crate::{haptics::Hand, HandLeft, HandRight, HP_MIXED_REALITY_PROFILE};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControllerProfile {
+    OculusTouch,
+    HpMixedReality,
+    Generic,
+}
+
+impl ControllerProfile {
+    fn from_path(path: &str) -> Self {
+        match path {
+            OCULUS_TOUCH_PROFILE => Self::OculusTouch,
+            HP_MIXED_REALITY_PROFILE => Self::HpMixedReality,
+            _ => Self::Generic,
+        }
+    }
+}
+
+// the mesh shown at the grip pose, child of HandLeft / HandRight
+#[derive(Component)]
+pub struct ControllerModel;
+
+#[derive(Resource, Default)]
+pub struct ControllerDisplay {
+    // show the debug overlay, the hand axes replace the controller models
+    pub debug_gizmos: bool,
+    left: Option<ControllerProfile>,
+    right: Option<ControllerProfile>,
+}
+
+// asks the runtime which interaction profile is active per hand and swaps the model if it changed
+pub fn update_controller_models(
+    mut commands: Commands,
+    session: Option<Res<OxrSession>>,
+    instance: Option<Res<OxrInstance>>,
+    mut display: ResMut<ControllerDisplay>,
+    left_hand: Query<Entity, With<HandLeft>>,
+    right_hand: Query<Entity, With<HandRight>>,
+    models: Query<(Entity, &ChildOf), With<ControllerModel>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let (Some(session), Some(instance)) = (session, instance) else {
+        return;
+    };
+    let visibility = if display.debug_gizmos {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+    for (hand, user_path, hand_entity) in [
+        (Hand::Left, "/user/hand/left", left_hand.single()),
+        (Hand::Right, "/user/hand/right", right_hand.single()),
+    ] {
+        let Ok(hand_entity) = hand_entity else {
+            continue;
+        };
+        let profile = instance
+            .string_to_path(user_path)
+            .and_then(|path| session.current_interaction_profile(path))
+            .ok()
+            .filter(|profile| *profile != openxr::Path::NULL)
+            .and_then(|profile| instance.path_to_string(profile).ok())
+            .map(|profile| ControllerProfile::from_path(&profile));
+        // only the debug flag counts as a change for show_controller_models
+        let display = display.bypass_change_detection();
+        let shown = match hand {
+            Hand::Left => &mut display.left,
+            Hand::Right => &mut display.right,
+        };
+        if *shown == profile {
+            continue;
+        }
+        info!("{:?} controller profile changed to {:?}", hand, profile);
+        *shown = profile;
+        for (model, child_of) in &models {
+            if child_of.parent() == hand_entity {
+                commands.entity(model).despawn();
+            }
+        }
+        if let Some(profile) = profile {
+            spawn_controller_model(
+                &mut commands,
+                hand_entity,
+                hand,
+                profile,
+                visibility,
+                &mut meshes,
+                &mut materials,
+            );
+        }
+    }
+}
+
+pub fn show_controller_models(
+    display: Res<ControllerDisplay>,
+    mut models: Query<&mut Visibility, With<ControllerModel>>,
+) {
+    if !display.is_changed() {
+        return;
+    }
+    for mut visibility in &mut models {
+        *visibility = if display.debug_gizmos {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+// simple models built from primitives, in grip pose space (-Z points out of the fist)
+fn spawn_controller_model(
+    commands: &mut Commands,
+    hand_entity: Entity,
+    hand: Hand,
+    profile: ControllerProfile,
+    visibility: Visibility,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    // mirror the asymmetric parts for the left hand
+    let side = match hand {
+        Hand::Left => -1.0,
+        Hand::Right => 1.0,
+    };
+    let body = materials.add(Color::srgb(0.1, 0.1, 0.12));
+    let accent = materials.add(match hand {
+        Hand::Left => Color::srgb(0.2, 0.3, 0.9),
+        Hand::Right => Color::srgb(0.9, 0.2, 0.2),
+    });
+    let mut parts: Vec<(Mesh3d, MeshMaterial3d<StandardMaterial>, Transform)> = vec![];
+    match profile {
+        ControllerProfile::OculusTouch => {
+            parts.push((
+                Mesh3d(meshes.add(Capsule3d::new(0.018, 0.07))),
+                MeshMaterial3d(body.clone()),
+                Transform::from_rotation(Quat::from_rotation_x(FRAC_PI_2)),
+            ));
+            parts.push((
+                Mesh3d(meshes.add(Torus::new(0.032, 0.046))),
+                MeshMaterial3d(accent),
+                Transform::from_xyz(side * 0.01, 0.03, -0.05)
+                    .with_rotation(Quat::from_rotation_x(-0.6)),
+            ));
+            parts.push((
+                Mesh3d(meshes.add(Cylinder::new(0.025, 0.012))),
+                MeshMaterial3d(body),
+                Transform::from_xyz(0.0, 0.02, -0.045).with_rotation(Quat::from_rotation_x(-0.6)),
+            ));
+        }
+        ControllerProfile::HpMixedReality => {
+            parts.push((
+                Mesh3d(meshes.add(Capsule3d::new(0.02, 0.09))),
+                MeshMaterial3d(body.clone()),
+                Transform::from_rotation(Quat::from_rotation_x(FRAC_PI_2)),
+            ));
+            parts.push((
+                Mesh3d(meshes.add(Torus::new(0.05, 0.065))),
+                MeshMaterial3d(accent),
+                Transform::from_xyz(side * 0.02, 0.045, -0.04),
+            ));
+            parts.push((
+                Mesh3d(meshes.add(Cylinder::new(0.028, 0.015))),
+                MeshMaterial3d(body),
+                Transform::from_xyz(0.0, 0.025, -0.06),
+            ));
+        }
+        ControllerProfile::Generic => {
+            parts.push((
+                Mesh3d(meshes.add(Cuboid::new(0.08, 0.025, 0.09))),
+                MeshMaterial3d(body),
+                Transform::from_rotation(Quat::from_rotation_z(side * -FRAC_PI_2)),
+            ));
+            parts.push((
+                Mesh3d(meshes.add(Sphere::new(0.015))),
+                MeshMaterial3d(accent),
+                Transform::from_xyz(0.0, 0.0, -0.06),
+            ));
+        }
+    }
+    commands.entity(hand_entity).with_children(|parent| {
+        parent
+            .spawn((ControllerModel, Transform::default(), visibility))
+            .with_children(|model| {
+                for part in parts {
+                    model.spawn(part);
+                }
+            });
+    });
+}
@@ -0,0 +1,184 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{
+    camera::primitives::Aabb,
+    color::palettes::css,
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    math::bounding::Aabb3d,
+    prelude::*,
+};
+use bevy_mod_xr::session::XrTrackingRoot;
+
+use crate::{
+    controllers::ControllerDisplay,
+    panel::{panel_text, set_panel_visible, spawn_world_panel, WorldPanel},
+    HandLeft, HandRight, HeadsetView,
+};
+
+// the text is rebuilt a few times per second, every frame would be unreadable anyway
+const PANEL_INTERVAL: f32 = 0.25;
+
+// shown while ControllerDisplay::debug_gizmos is set, which also hides the controller models
+#[derive(Resource)]
+pub struct DebugOverlay {
+    panel: Option<WorldPanel>,
+    text: Option<Entity>,
+    timer: Timer,
+    // collected by draw_debug_overlay for the panel
+    root: Option<Transform>,
+    head: Option<Transform>,
+    bounds: Option<Aabb3d>,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self {
+            panel: None,
+            text: None,
+            timer: Timer::from_seconds(PANEL_INTERVAL, TimerMode::Repeating),
+            root: None,
+            head: None,
+            bounds: None,
+        }
+    }
+}
+
+// world space bounds of everything below the scene roots
+pub fn scene_bounds(
+    models: &Query<Entity, With<SceneRoot>>,
+    children: &Query<&Children>,
+    bounds: &Query<(&Aabb, &GlobalTransform)>,
+) -> Option<Aabb3d> {
+    models
+        .iter()
+        .flat_map(|model| children.iter_descendants(model))
+        .filter_map(|child| bounds.get(child).ok())
+        .map(|(aabb, global)| {
+            let center = global.transform_point(aabb.center.into());
+            // the extents of a rotated box along the world axes
+            let half = global.affine().matrix3.abs() * aabb.half_extents;
+            Aabb3d::new(center, half)
+        })
+        .reduce(|a, b| Aabb3d {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        })
+}
+
+pub fn draw_debug_overlay(
+    mut overlay: ResMut<DebugOverlay>,
+    display: Res<ControllerDisplay>,
+    mut gizmos: Gizmos,
+    hands: Query<&GlobalTransform, Or<(With<HandLeft>, With<HandRight>)>>,
+    root: Query<&Transform, With<XrTrackingRoot>>,
+    headset: Query<&Transform, (With<HeadsetView>, Without<XrTrackingRoot>)>,
+    models: Query<Entity, With<SceneRoot>>,
+    children: Query<&Children>,
+    bounds: Query<(&Aabb, &GlobalTransform)>,
+) {
+    if !display.debug_gizmos {
+        return;
+    }
+    for hand in &hands {
+        gizmos.axes(*hand, 0.1);
+    }
+    overlay.root = root.single().ok().copied();
+    // the headset pose is relative to the tracking root
+    overlay.head = match (overlay.root, headset.single()) {
+        (Some(root), Ok(headset)) => Some(root.mul_transform(*headset)),
+        _ => None,
+    };
+    if let Some(root) = overlay.root {
+        gizmos.axes(root, 0.3);
+        gizmos.circle(
+            Isometry3d::new(root.translation, Quat::from_rotation_x(FRAC_PI_2)),
+            0.3,
+            css::YELLOW,
+        );
+    }
+    if let Some(head) = overlay.head {
+        // a bit in front of the eyes, otherwise the axes are behind the near plane
+        gizmos.axes(head.with_translation(head.transform_point(Vec3::NEG_Z * 0.5)), 0.05);
+    }
+    overlay.bounds = scene_bounds(&models, &children, &bounds);
+    if let Some(bounds) = overlay.bounds {
+        gizmos.aabb_3d(bounds, Transform::IDENTITY, css::ORANGE);
+    }
+}
+
+// a panel above the right controller, the left one holds the passthrough style menu
+pub fn update_debug_panel(
+    mut commands: Commands,
+    mut overlay: ResMut<DebugOverlay>,
+    display: Res<ControllerDisplay>,
+    time: Res<Time>,
+    diagnostics: Res<DiagnosticsStore>,
+    right_hand: Query<Entity, With<HandRight>>,
+    mut texts: Query<&mut Text>,
+    mut cameras: Query<&mut Camera>,
+    targets: Query<&UiTargetCamera>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if display.is_changed() {
+        if let Some(panel) = &overlay.panel {
+            set_panel_visible(&mut commands, panel, &mut cameras, &targets, display.debug_gizmos);
+        } else if display.debug_gizmos {
+            let panel = spawn_world_panel(
+                &mut commands,
+                &mut images,
+                &mut meshes,
+                &mut materials,
+                UVec2::new(512, 256),
+                0.2,
+            );
+            commands.entity(panel.quad).insert(
+                Transform::from_xyz(0.0, 0.12, -0.05).with_rotation(Quat::from_rotation_x(-0.5)),
+            );
+            if let Ok(hand) = right_hand.single() {
+                commands.entity(hand).add_child(panel.quad);
+            }
+            let text = commands.spawn(panel_text("", 20.0)).id();
+            commands.entity(panel.root).add_child(text);
+            overlay.text = Some(text);
+            overlay.panel = Some(panel);
+        }
+    }
+    if !display.debug_gizmos || !overlay.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Some(mut text) = overlay.text.and_then(|text| texts.get_mut(text).ok()) else {
+        return;
+    };
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+    let frame_time = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|frame_time| frame_time.smoothed())
+        .unwrap_or_default();
+    let mut lines = vec![format!("{:.0} fps, {:.1} ms", fps, frame_time)];
+    if let Some(root) = overlay.root {
+        let (yaw, _, _) = root.rotation.to_euler(EulerRot::YXZ);
+        lines.push(format!(
+            "root {:.2} {:.2} {:.2}, yaw {:.0}°",
+            root.translation.x,
+            root.translation.y,
+            root.translation.z,
+            yaw.to_degrees()
+        ));
+    }
+    if let Some(head) = overlay.head {
+        lines.push(format!(
+            "head {:.2} {:.2} {:.2}",
+            head.translation.x, head.translation.y, head.translation.z
+        ));
+    }
+    if let Some(bounds) = overlay.bounds {
+        let size = bounds.max - bounds.min;
+        lines.push(format!("scene {:.2} x {:.2} x {:.2} m", size.x, size.y, size.z));
+    }
+    text.0 = lines.join("\n");
+}
@@ -0,0 +1,399 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    color::{ColorToComponents, ColorToPacked},
+    core_pipeline::Skybox,
+    image::{ImageAddressMode, ImageSampler, ImageSamplerDescriptor},
+    math::Affine2,
+    prelude::*,
+    render::render_resource::{
+        Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+    },
+};
+use half::f16;
+use bevy_mod_xr::session::XrTrackingRoot;
+use schminput::prelude::*;
+
+use crate::{
+    asset_handler::ASSET_ELEMENTS, capabilities::XrCapabilities, passthrough::PassthroughMode,
+    settings::UserSettings, MoveActions,
+};
+
+// the edge length of a generated sky cubemap face, must be a power of two for the light filtering
+const SKY_SIZE: u32 = 128;
+// the ground texture repeats every few meters
+const GROUND_TEXTURE_SIZE: u32 = 256;
+const GROUND_SIZE: f32 = 40.0;
+const GROUND_TILE: f32 = 4.0;
+
+pub enum Ground {
+    // gizmo grid following the player, looks infinite
+    Grid { spacing: f32, color: Color },
+    // the generated ground texture tinted with the color
+    Plane { color: Color },
+}
+
+// a sky blended from the horizon up to the zenith and down to the ground
+pub struct SkyGradient {
+    pub zenith: Color,
+    pub horizon: Color,
+    pub ground: Color,
+    // the colors are scaled by this, above 1.0 is brighter than white in the hdr cubemap
+    pub intensity: f32,
+}
+
+impl SkyGradient {
+    fn color(&self, y: f32) -> Color {
+        let (target, amount) = if y >= 0.0 { (self.zenith, y) } else { (self.ground, -y) };
+        // the horizon band stays narrow, like a real sky
+        self.horizon.mix(&target, amount.sqrt())
+    }
+
+    // a cubemap used as skybox and as source of the environment light
+    fn cubemap(&self) -> Image {
+        let size = SKY_SIZE as usize;
+        // 4 half floats per pixel
+        let mut data = Vec::with_capacity(size * size * 6 * 8);
+        // faces in the order +x, -x, +y, -y, +z, -z, only the height of the direction matters
+        for face in 0..6 {
+            for row in 0..size {
+                for column in 0..size {
+                    let u = (column as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+                    let v = (row as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+                    let direction = match face {
+                        0 => Vec3::new(1.0, -v, -u),
+                        1 => Vec3::new(-1.0, -v, u),
+                        2 => Vec3::new(u, 1.0, v),
+                        3 => Vec3::new(u, -1.0, -v),
+                        4 => Vec3::new(u, -v, 1.0),
+                        _ => Vec3::new(-u, -v, -1.0),
+                    };
+                    let color = self.color(direction.normalize().y).to_linear() * self.intensity;
+                    for channel in color.to_f32_array() {
+                        data.extend(f16::from_f32(channel).to_le_bytes());
+                    }
+                }
+            }
+        }
+        let mut image = Image::new(
+            Extent3d {
+                width: SKY_SIZE,
+                height: SKY_SIZE,
+                depth_or_array_layers: 6,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba16Float,
+            RenderAssetUsages::RENDER_WORLD,
+        );
+        image.texture_view_descriptor = Some(TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::Cube),
+            ..default()
+        });
+        image
+    }
+}
+
+// deterministic noise in 0.0 - 1.0 for a lattice point
+fn lattice_noise(x: u32, y: u32) -> f32 {
+    let mut hash = x.wrapping_mul(0x8da6_b343) ^ y.wrapping_mul(0xd816_3841);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 16;
+    hash as f32 / u32::MAX as f32
+}
+
+// smoothly interpolated lattice noise that wraps around after `period` cells, so the texture tiles
+fn value_noise(u: f32, v: f32, period: u32) -> f32 {
+    let (x, y) = (u * period as f32, v * period as f32);
+    let (x0, y0) = (x.floor() as u32 % period, y.floor() as u32 % period);
+    let (x1, y1) = ((x0 + 1) % period, (y0 + 1) % period);
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let mix = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let (tx, ty) = (smooth(x.fract()), smooth(y.fract()));
+    let top = mix(lattice_noise(x0, y0), lattice_noise(x1, y0), tx);
+    let bottom = mix(lattice_noise(x0, y1), lattice_noise(x1, y1), tx);
+    mix(top, bottom, ty)
+}
+
+// a mottled, tileable gray texture, the ground color tints it
+fn ground_texture() -> Image {
+    let size = GROUND_TEXTURE_SIZE;
+    let mut data = Vec::with_capacity((size * size * 4) as usize);
+    for row in 0..size {
+        for column in 0..size {
+            let (u, v) = (column as f32 / size as f32, row as f32 / size as f32);
+            // coarse patches with finer detail on top
+            let noise = value_noise(u, v, 4) * 0.5
+                + value_noise(u, v, 16) * 0.3
+                + value_noise(u, v, 64) * 0.2;
+            let value = 0.6 + noise * 0.4;
+            data.extend(Color::linear_rgb(value, value, value).to_srgba().to_u8_array());
+        }
+    }
+    let mut image = Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        ..ImageSamplerDescriptor::linear()
+    });
+    image
+}
+
+pub struct EnvironmentPreset {
+    pub name: &'static str,
+    // clear color, the sky gradient covers it
+    pub sky_color: Color,
+    // shown as skybox and lights the model through the environment map
+    pub sky: Option<SkyGradient>,
+    pub ground: Ground,
+    // name of a lighting preset, None uses the global one
+    pub lighting: Option<&'static str>,
+}
+
+pub static ENVIRONMENT_PRESETS: &[EnvironmentPreset] = &[
+    EnvironmentPreset {
+        name: "studio",
+        sky_color: Color::srgb(0.18, 0.18, 0.2),
+        // a bright ceiling like the softboxes of a photo studio
+        sky: Some(SkyGradient {
+            zenith: Color::srgb(0.9, 0.9, 0.92),
+            horizon: Color::srgb(0.2, 0.2, 0.22),
+            ground: Color::srgb(0.12, 0.12, 0.13),
+            intensity: 1.5,
+        }),
+        ground: Ground::Grid {
+            spacing: 0.5,
+            color: Color::srgb(0.45, 0.45, 0.5),
+        },
+        lighting: Some("studio"),
+    },
+    EnvironmentPreset {
+        name: "outdoor",
+        sky_color: Color::srgb(0.45, 0.6, 0.8),
+        sky: Some(SkyGradient {
+            zenith: Color::srgb(0.2, 0.4, 0.85),
+            horizon: Color::srgb(0.75, 0.82, 0.9),
+            ground: Color::srgb(0.25, 0.3, 0.2),
+            intensity: 2.0,
+        }),
+        ground: Ground::Plane {
+            color: Color::srgb(0.3, 0.42, 0.25),
+        },
+        lighting: Some("outdoor_sun"),
+    },
+    EnvironmentPreset {
+        name: "night",
+        sky_color: Color::srgb(0.01, 0.01, 0.03),
+        sky: Some(SkyGradient {
+            zenith: Color::srgb(0.0, 0.0, 0.02),
+            horizon: Color::srgb(0.04, 0.05, 0.12),
+            ground: Color::srgb(0.01, 0.01, 0.02),
+            intensity: 1.0,
+        }),
+        ground: Ground::Grid {
+            spacing: 1.0,
+            color: Color::srgb(0.1, 0.15, 0.3),
+        },
+        lighting: None,
+    },
+];
+
+fn preset_index(name: &str) -> Option<usize> {
+    ENVIRONMENT_PRESETS.iter().position(|preset| preset.name == name)
+}
+
+#[derive(Resource)]
+pub struct EnvironmentSettings {
+    // used for catalog entries without their own environment
+    pub global: usize,
+    // generated once per preset with a sky gradient
+    skies: Vec<Option<Handle<Image>>>,
+    cycle_action: Entity,
+    cycle_released: bool,
+}
+
+// the preset that is currently shown, None in passthrough mode
+#[derive(Resource, Default, PartialEq)]
+pub struct ActiveEnvironment(pub Option<usize>);
+
+#[derive(Component)]
+pub struct EnvironmentGround;
+
+pub fn setup_environment(
+    mut commands: Commands,
+    capabilities: Res<XrCapabilities>,
+    settings: Res<UserSettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let set = commands.spawn(ActionSet::new("environment", "Environment", 1)).id();
+    let cycle_action = commands
+        .spawn((
+            Action::new("cycle_environment", "Cycle Environment", set),
+            capabilities.bindings("/user/hand/right/input/thumbstick/click"),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyV)),
+            BoolActionValue::new(),
+        ))
+        .id();
+    let global = settings
+        .get::<String>("environment")
+        .and_then(|name| preset_index(&name))
+        .unwrap_or(0);
+    let skies = ENVIRONMENT_PRESETS
+        .iter()
+        .map(|preset| preset.sky.as_ref().map(|sky| images.add(sky.cubemap())))
+        .collect();
+    commands.insert_resource(EnvironmentSettings {
+        global,
+        skies,
+        cycle_action,
+        cycle_released: true,
+    });
+    commands.spawn((
+        EnvironmentGround,
+        Mesh3d(meshes.add(Plane3d::default().mesh().size(GROUND_SIZE, GROUND_SIZE))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color_texture: Some(images.add(ground_texture())),
+            uv_transform: Affine2::from_scale(Vec2::splat(GROUND_SIZE / GROUND_TILE)),
+            perceptual_roughness: 0.9,
+            ..default()
+        })),
+        Transform::default(),
+        Visibility::Hidden,
+    ));
+}
+
+pub fn cycle_environment(
+    mut environment: ResMut<EnvironmentSettings>,
+    bool_value: Query<&BoolActionValue>,
+    mut settings: ResMut<UserSettings>,
+) {
+    if !bool_value.get(environment.cycle_action).unwrap().any {
+        environment.bypass_change_detection().cycle_released = true;
+        return;
+    }
+    if !environment.cycle_released {
+        return;
+    }
+    environment.cycle_released = false;
+    environment.global = (environment.global + 1) % ENVIRONMENT_PRESETS.len();
+    let name = ENVIRONMENT_PRESETS[environment.global].name;
+    info!("environment: {}", name);
+    settings.set("environment", name);
+}
+
+// picks the preset of the shown catalog entry or the global one
+pub fn select_environment(
+    environment: Res<EnvironmentSettings>,
+    passthrough: Res<PassthroughMode>,
+    move_actions: Res<MoveActions>,
+    mut active: ResMut<ActiveEnvironment>,
+) {
+    let selected = if passthrough.enabled {
+        None
+    } else {
+        let index = ASSET_ELEMENTS
+            .get(move_actions.shown_scene)
+            .and_then(|asset| asset.environment)
+            .and_then(preset_index)
+            .unwrap_or(environment.global);
+        Some(index)
+    };
+    active.set_if_neq(ActiveEnvironment(selected));
+}
+
+pub fn apply_environment(
+    active: Res<ActiveEnvironment>,
+    environment: Res<EnvironmentSettings>,
+    mut commands: Commands,
+    mut clear_color: ResMut<ClearColor>,
+    mut ground: Query<
+        (&mut Visibility, &MeshMaterial3d<StandardMaterial>),
+        With<EnvironmentGround>,
+    >,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    cameras: Query<Entity, With<Camera3d>>,
+    new_cameras: Query<(), Added<Camera3d>>,
+) {
+    // xr cameras are spawned with the session, they need the skybox too
+    if !active.is_changed() && new_cameras.is_empty() {
+        return;
+    }
+    let preset = active.0.map(|index| &ENVIRONMENT_PRESETS[index]);
+    clear_color.0 = preset.map_or(Color::NONE, |preset| preset.sky_color);
+    let sky = active.0.and_then(|index| environment.skies[index].clone());
+    for camera in &cameras {
+        // the generated light is only rebuilt without an EnvironmentMapLight, so always drop it
+        commands
+            .entity(camera)
+            .remove::<(Skybox, GeneratedEnvironmentMapLight, EnvironmentMapLight)>();
+        if let Some(image) = &sky {
+            // filtered on the gpu into a diffuse irradiance and a specular map
+            commands.entity(camera).insert((
+                Skybox {
+                    image: image.clone(),
+                    brightness: 1000.0,
+                    ..default()
+                },
+                GeneratedEnvironmentMapLight {
+                    environment_map: image.clone(),
+                    intensity: 1000.0,
+                    ..default()
+                },
+            ));
+        }
+    }
+    let Some(preset) = preset else {
+        for (mut visibility, _) in &mut ground {
+            *visibility = Visibility::Hidden;
+        }
+        return;
+    };
+    for (mut visibility, material) in &mut ground {
+        if let Ground::Plane { color } = &preset.ground {
+            *visibility = Visibility::Inherited;
+            if let Some(material) = materials.get_mut(&material.0) {
+                material.base_color = *color;
+            }
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+pub fn draw_environment_grid(
+    active: Res<ActiveEnvironment>,
+    root: Query<&GlobalTransform, With<XrTrackingRoot>>,
+    mut gizmos: Gizmos,
+) {
+    let ground = active.0.map(|index| &ENVIRONMENT_PRESETS[index].ground);
+    let Some(Ground::Grid { spacing, color }) = ground else {
+        return;
+    };
+    // snap the grid to its cells around the player so it seems endless
+    let center = root
+        .single()
+        .map(|root| root.translation())
+        .unwrap_or_default();
+    let snapped = (center / *spacing).round() * *spacing;
+    gizmos.grid(
+        Isometry3d::new(
+            Vec3::new(snapped.x, 0.0, snapped.z),
+            Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2),
+        ),
+        UVec2::splat(40),
+        Vec2::splat(*spacing),
+        *color,
+    );
+}
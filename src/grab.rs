@@ -0,0 +1,319 @@
+use bevy::{camera::primitives::Aabb, prelude::*};
+use schminput::prelude::*;
+
+use crate::{
+    asset_handler::MAX_ASSET_ELEMENTS,
+    capabilities::XrCapabilities,
+    haptics::Hand,
+    pointer::LaserPointers,
+    sfx::{Sfx, SfxEvent, SfxPosition},
+    HandLeft, HandRight, MoveActions,
+};
+
+// how close (in meters) the hand has to be to the model bounds to grab it
+const GRAB_DISTANCE: f32 = 0.1;
+const MIN_SCALE: f32 = 0.05;
+const MAX_SCALE: f32 = 20.0;
+
+#[derive(Resource, Clone, Copy)]
+pub struct GrabActions {
+    left_grip: Entity,
+    right_grip: Entity,
+    reset_model: Entity,
+}
+
+// the transform of every catalog entry, restored when the entry is shown again
+#[derive(Resource)]
+pub struct ModelTransforms {
+    transforms: Vec<Transform>,
+}
+
+impl Default for ModelTransforms {
+    fn default() -> Self {
+        Self {
+            transforms: vec![Transform::default(); MAX_ASSET_ELEMENTS],
+        }
+    }
+}
+
+impl ModelTransforms {
+    pub fn get(&self, index: usize) -> Transform {
+        self.transforms.get(index).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, index: usize, transform: Transform) {
+        if let Some(stored) = self.transforms.get_mut(index) {
+            *stored = transform;
+        }
+    }
+}
+
+#[derive(Default)]
+enum Grab {
+    #[default]
+    None,
+    // model transform relative to the hand
+    OneHand { hand: Hand, offset: Transform },
+    // hand positions and model transform at the moment the second hand grabbed
+    TwoHands { left: Vec3, right: Vec3, model: Transform },
+}
+
+#[derive(Resource, Default)]
+pub struct GrabState {
+    grab: Grab,
+    left_pressed: bool,
+    right_pressed: bool,
+    reset_pressed: bool,
+}
+
+impl GrabState {
+    pub fn is_grabbing(&self) -> bool {
+        !matches!(self.grab, Grab::None)
+    }
+
+    // lets go of the model, e.g. before it is despawned, true if it was held
+    pub fn release(&mut self) -> bool {
+        let holding = self.is_grabbing();
+        self.grab = Grab::None;
+        holding
+    }
+}
+
+pub fn setup_grab_actions(mut cmds: Commands, capabilities: Res<XrCapabilities>) {
+    let set = cmds.spawn(ActionSet::new("grab", "Grab", 1)).id();
+    let left_grip = cmds
+        .spawn((
+            Action::new("grab_left", "Grab Left", set),
+            capabilities.bindings("/user/hand/left/input/squeeze/value"),
+            F32ActionValue::new(),
+        ))
+        .id();
+    let right_grip = cmds
+        .spawn((
+            Action::new("grab_right", "Grab Right", set),
+            capabilities.bindings("/user/hand/right/input/squeeze/value"),
+            F32ActionValue::new(),
+        ))
+        .id();
+    let reset_model = cmds
+        .spawn((
+            Action::new("reset_model", "Reset Model", set),
+            capabilities.bindings("/user/hand/left/input/x/click"),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyR)),
+            BoolActionValue::new(),
+        ))
+        .id();
+    cmds.insert_resource(GrabActions {
+        left_grip,
+        right_grip,
+        reset_model,
+    });
+}
+
+pub fn grab_model(
+    grab_actions: Res<GrabActions>,
+    move_actions: Res<MoveActions>,
+    f32_value: Query<&F32ActionValue>,
+    lasers: Res<LaserPointers>,
+    mut state: ResMut<GrabState>,
+    mut model_transforms: ResMut<ModelTransforms>,
+    mut model: Query<(Entity, &mut Transform), With<SceneRoot>>,
+    left_hand: Query<&GlobalTransform, With<HandLeft>>,
+    right_hand: Query<&GlobalTransform, With<HandRight>>,
+    children: Query<&Children>,
+    bounds: Query<(&Aabb, &GlobalTransform)>,
+    mut sfx: MessageWriter<Sfx>,
+) {
+    let Ok((model_entity, mut model_transform)) = model.single_mut() else {
+        state.grab = Grab::None;
+        return;
+    };
+    let left_pressed = f32_value.get(grab_actions.left_grip).unwrap().any > 0.5;
+    let right_pressed = f32_value.get(grab_actions.right_grip).unwrap().any > 0.5;
+    let left = left_hand.single().map(|hand| hand.compute_transform()).ok();
+    let right = right_hand.single().map(|hand| hand.compute_transform()).ok();
+
+    // the model can be grabbed when the hand is at its bounds or the laser points at it
+    let can_grab = |hand: Hand, pose: Option<Transform>| {
+        let near = pose.is_some_and(|pose| {
+            children.iter_descendants(model_entity).any(|child| {
+                bounds.get(child).is_ok_and(|(aabb, global)| {
+                    let local = global.affine().inverse().transform_point3(pose.translation);
+                    let scale = global.scale().max_element().max(f32::EPSILON);
+                    let outside = (Vec3A::from(local) - aabb.center).abs() - aabb.half_extents;
+                    outside.max(Vec3A::ZERO).length() * scale <= GRAB_DISTANCE
+                })
+            })
+        });
+        let pointing = lasers.hit(hand).is_some_and(|hit| {
+            hit.entity == model_entity
+                || children.iter_descendants(model_entity).any(|child| child == hit.entity)
+        });
+        near || pointing
+    };
+    let left_grabbed = left_pressed && !state.left_pressed;
+    let right_grabbed = right_pressed && !state.right_pressed;
+    let released = (!left_pressed && state.left_pressed) || (!right_pressed && state.right_pressed);
+    state.left_pressed = left_pressed;
+    state.right_pressed = right_pressed;
+
+    let holding_left = matches!(state.grab, Grab::OneHand { hand: Hand::Left, .. });
+    let holding_right = matches!(state.grab, Grab::OneHand { hand: Hand::Right, .. });
+    let holding = state.is_grabbing();
+    if let (Some(left), Some(right)) = (left, right)
+        && ((holding_left && right_grabbed) || (holding_right && left_grabbed))
+    {
+        state.grab = Grab::TwoHands {
+            left: left.translation,
+            right: right.translation,
+            model: *model_transform,
+        };
+    } else if released || (!holding && (left_grabbed || right_grabbed)) {
+        // start a one handed grab, also when one of two hands lets go
+        let grabbing = match (left_pressed, right_pressed) {
+            (true, false) => Some((Hand::Left, left)),
+            (false, true) => Some((Hand::Right, right)),
+            (true, true) if !holding && left_grabbed => Some((Hand::Left, left)),
+            (true, true) if !holding => Some((Hand::Right, right)),
+            _ => None,
+        };
+        state.grab = match grabbing {
+            Some((hand, Some(pose))) if holding || can_grab(hand, Some(pose)) => {
+                if !holding {
+                    sfx.write(Sfx::new(SfxEvent::Grab, SfxPosition::Hand(hand)));
+                }
+                Grab::OneHand {
+                    hand,
+                    offset: Transform::from_matrix(
+                        pose.to_matrix().inverse() * model_transform.to_matrix(),
+                    ),
+                }
+            }
+            _ => {
+                if holding {
+                    info!("model released");
+                    model_transforms.set(move_actions.shown_scene, *model_transform);
+                    sfx.write(Sfx::new(
+                        SfxEvent::Release,
+                        SfxPosition::Point(model_transform.translation),
+                    ));
+                }
+                Grab::None
+            }
+        };
+    }
+
+    match &state.grab {
+        Grab::None => {}
+        Grab::OneHand { hand, offset } => {
+            let pose = match hand {
+                Hand::Left => left,
+                Hand::Right => right,
+            };
+            if let Some(pose) = pose {
+                *model_transform = Transform::from_matrix(pose.to_matrix() * offset.to_matrix());
+            }
+        }
+        Grab::TwoHands {
+            left: start_left,
+            right: start_right,
+            model: start_model,
+        } => {
+            if let (Some(left), Some(right)) = (left, right) {
+                *model_transform = two_hand_transform(
+                    (*start_left, *start_right),
+                    *start_model,
+                    (left.translation, right.translation),
+                );
+            }
+        }
+    }
+}
+
+// the model follows the hands like a rod held at both ends, moved by the center,
+// turned by the direction and scaled by the distance between the hands
+fn two_hand_transform(
+    start: (Vec3, Vec3),
+    start_model: Transform,
+    hands: (Vec3, Vec3),
+) -> Transform {
+    let start_span = start.1 - start.0;
+    let span = hands.1 - hands.0;
+    let start_center = (start.0 + start.1) * 0.5;
+    let center = (hands.0 + hands.1) * 0.5;
+    let ratio = span.length() / start_span.length().max(f32::EPSILON);
+    let rotation =
+        Quat::from_rotation_arc(start_span.normalize_or(Vec3::X), span.normalize_or(Vec3::X));
+    let scale =
+        (start_model.scale * ratio).clamp(Vec3::splat(MIN_SCALE), Vec3::splat(MAX_SCALE));
+    let ratio = scale.x / start_model.scale.x;
+    Transform {
+        translation: center + rotation * (start_model.translation - start_center) * ratio,
+        rotation: rotation * start_model.rotation,
+        scale,
+    }
+}
+
+pub fn reset_model(
+    grab_actions: Res<GrabActions>,
+    move_actions: Res<MoveActions>,
+    bool_value: Query<&BoolActionValue>,
+    mut state: ResMut<GrabState>,
+    mut model_transforms: ResMut<ModelTransforms>,
+    mut model: Query<&mut Transform, With<SceneRoot>>,
+    mut sfx: MessageWriter<Sfx>,
+) {
+    let pressed = bool_value.get(grab_actions.reset_model).unwrap().any;
+    let just_pressed = pressed && !state.reset_pressed;
+    state.reset_pressed = pressed;
+    if !just_pressed {
+        return;
+    }
+    state.grab = Grab::None;
+    info!("reset model transform of scene {}", move_actions.shown_scene);
+    model_transforms.set(move_actions.shown_scene, Transform::default());
+    for mut transform in &mut model {
+        *transform = Transform::default();
+    }
+    sfx.write(Sfx::new(SfxEvent::ResetModel, SfxPosition::Point(Vec3::ZERO)));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    #[test]
+    fn two_hands_scale_with_their_distance() {
+        let start = (Vec3::new(-0.5, 1.0, 0.0), Vec3::new(0.5, 1.0, 0.0));
+        let hands = (Vec3::new(-1.0, 1.0, 0.0), Vec3::new(1.0, 1.0, 0.0));
+        let model = Transform::from_xyz(0.0, 1.0, -1.0);
+        let moved = two_hand_transform(start, model, hands);
+        assert!(moved.scale.abs_diff_eq(Vec3::splat(2.0), 1e-5));
+        // the model keeps its place relative to the hands, twice as far away
+        assert!(moved.translation.abs_diff_eq(Vec3::new(0.0, 1.0, -2.0), 1e-5));
+    }
+
+    #[test]
+    fn two_hands_scale_is_clamped() {
+        let start = (Vec3::new(-0.5, 0.0, 0.0), Vec3::new(0.5, 0.0, 0.0));
+        let model = Transform::default();
+        let together = two_hand_transform(start, model, (Vec3::ZERO, Vec3::new(0.001, 0.0, 0.0)));
+        assert!(together.scale.abs_diff_eq(Vec3::splat(MIN_SCALE), 1e-6));
+        let apart = two_hand_transform(start, model, (Vec3::ZERO, Vec3::new(100.0, 0.0, 0.0)));
+        assert!(apart.scale.abs_diff_eq(Vec3::splat(MAX_SCALE), 1e-6));
+    }
+
+    #[test]
+    fn two_hands_rotate_around_the_center() {
+        let start = (Vec3::new(-0.5, 0.0, 0.0), Vec3::new(0.5, 0.0, 0.0));
+        // the right hand moved from +x to -z, a quarter turn around y
+        let hands = (Vec3::new(0.0, 0.0, 0.5), Vec3::new(0.0, 0.0, -0.5));
+        let model = Transform::from_xyz(1.0, 0.0, 0.0);
+        let moved = two_hand_transform(start, model, hands);
+        assert!(moved.rotation.abs_diff_eq(Quat::from_rotation_y(FRAC_PI_2), 1e-5));
+        assert!(moved.translation.abs_diff_eq(Vec3::new(0.0, 0.0, -1.0), 1e-5));
+        assert!(moved.scale.abs_diff_eq(Vec3::ONE, 1e-5));
+    }
+}
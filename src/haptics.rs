@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_mod_openxr::{
+    action_binding::OxrSuggestActionBinding,
+    action_set_attaching::OxrAttachActionSet,
+    action_set_syncing::OxrSyncActionSet,
+    resources::OxrInstance,
+    session::OxrSession,
+};
+use schminput::prelude::OCULUS_TOUCH_PROFILE;
+
+use crate::{capabilities::XrCapabilities, HP_MIXED_REALITY_PROFILE};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+// send this message to let a controller vibrate
+#[derive(Message, Clone, Copy, Debug)]
+pub struct Haptics {
+    pub hand: Hand,
+    // 0.0 - 1.0
+    pub amplitude: f32,
+    pub duration: Duration,
+    // in Hz, None lets the runtime choose
+    pub frequency: Option<f32>,
+}
+
+impl Haptics {
+    // very short and light, e.g. for snap turn
+    pub fn tick(hand: Hand) -> Self {
+        Self {
+            hand,
+            amplitude: 0.3,
+            duration: Duration::from_millis(20),
+            frequency: None,
+        }
+    }
+
+    // a noticeable confirmation, e.g. for a scene switch
+    pub fn pulse(hand: Hand) -> Self {
+        Self {
+            hand,
+            amplitude: 0.8,
+            duration: Duration::from_millis(120),
+            frequency: None,
+        }
+    }
+}
+
+// schminput has no haptic outputs, so we use our own openxr action set for them
+#[derive(Resource)]
+pub struct HapticActions {
+    set: openxr::ActionSet,
+    left: openxr::Action<openxr::Haptic>,
+    right: openxr::Action<openxr::Haptic>,
+}
+
+// without the resource the haptic systems don't run and Haptics messages are dropped
+pub fn create_haptic_actions(instance: Res<OxrInstance>, mut commands: Commands) {
+    let actions = instance
+        .create_action_set("haptics", "Haptics", 0)
+        .and_then(|set| {
+            let left = set.create_action::<openxr::Haptic>("haptic_left", "Left Haptic", &[])?;
+            let right = set.create_action::<openxr::Haptic>("haptic_right", "Right Haptic", &[])?;
+            Ok(HapticActions { set, left, right })
+        });
+    match actions {
+        Ok(actions) => commands.insert_resource(actions),
+        Err(err) => warn!("unable to create the haptic actions, haptics disabled: {}", err),
+    }
+}
+
+pub fn suggest_haptic_bindings(
+    actions: Res<HapticActions>,
+    capabilities: Res<XrCapabilities>,
+    mut bindings: MessageWriter<OxrSuggestActionBinding>,
+) {
+    let mut profiles = vec![OCULUS_TOUCH_PROFILE];
+    if capabilities.hp_controller {
+        profiles.push(HP_MIXED_REALITY_PROFILE);
+    }
+    for profile in profiles {
+        bindings.write(OxrSuggestActionBinding {
+            action: actions.left.as_raw(),
+            interaction_profile: profile.into(),
+            bindings: vec!["/user/hand/left/output/haptic".into()],
+        });
+        bindings.write(OxrSuggestActionBinding {
+            action: actions.right.as_raw(),
+            interaction_profile: profile.into(),
+            bindings: vec!["/user/hand/right/output/haptic".into()],
+        });
+    }
+}
+
+pub fn attach_haptic_set(actions: Res<HapticActions>, mut attach: MessageWriter<OxrAttachActionSet>) {
+    attach.write(OxrAttachActionSet(actions.set.clone()));
+}
+
+// haptic actions only work while their set is active
+pub fn sync_haptic_set(actions: Res<HapticActions>, mut sync: MessageWriter<OxrSyncActionSet>) {
+    sync.write(OxrSyncActionSet(actions.set.clone()));
+}
+
+pub fn play_haptics(
+    mut haptics: MessageReader<Haptics>,
+    actions: Option<Res<HapticActions>>,
+    session: Option<Res<OxrSession>>,
+) {
+    let (Some(actions), Some(session)) = (actions, session) else {
+        // no xr session, nothing to vibrate
+        haptics.clear();
+        return;
+    };
+    for haptic in haptics.read() {
+        let action = match haptic.hand {
+            Hand::Left => &actions.left,
+            Hand::Right => &actions.right,
+        };
+        let vibration = openxr::HapticVibration::new()
+            .amplitude(haptic.amplitude.clamp(0.0, 1.0))
+            .duration(openxr::Duration::from_nanos(haptic.duration.as_nanos() as i64))
+            .frequency(haptic.frequency.unwrap_or(openxr::FREQUENCY_UNSPECIFIED));
+        if let Err(err) = action.apply_feedback(&session, openxr::Path::NULL, &vibration) {
+            warn_once!("unable to apply haptic feedback: {}", err);
+        }
+    }
+}
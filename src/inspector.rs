@@ -0,0 +1,497 @@
+use std::any::TypeId;
+
+use bevy::{
+    camera::RenderTarget,
+    ecs::reflect::{AppTypeRegistry, ReflectResource},
+    prelude::*,
+    reflect::{ReflectPath, ReflectRef, TypeRegistry},
+    window::WindowRef,
+};
+use schminput::prelude::*;
+
+use crate::{panel::panel_text, Inspected};
+
+// rows shown around the selection, the rest is scrolled away
+const LISTED_ENTRIES: usize = 12;
+const LISTED_FIELDS: usize = 20;
+// nested deeper than this is too much for a text list
+const MAX_DEPTH: usize = 4;
+const MAX_LIST_ITEMS: usize = 16;
+
+// an editable value inside a component or resource, found through reflection
+#[derive(Clone, Copy, Debug)]
+enum Leaf {
+    F32(f32),
+    F64(f64),
+    Bool(bool),
+    Usize(usize),
+    U32(u32),
+    I32(i32),
+    // a rotation is edited as yaw, pitch and roll in degrees
+    Euler(f32),
+}
+
+impl Leaf {
+    fn read(value: &dyn PartialReflect) -> Option<Self> {
+        let value = value.try_as_reflect()?.as_any();
+        if let Some(value) = value.downcast_ref::<f32>() {
+            Some(Leaf::F32(*value))
+        } else if let Some(value) = value.downcast_ref::<f64>() {
+            Some(Leaf::F64(*value))
+        } else if let Some(value) = value.downcast_ref::<bool>() {
+            Some(Leaf::Bool(*value))
+        } else if let Some(value) = value.downcast_ref::<usize>() {
+            Some(Leaf::Usize(*value))
+        } else if let Some(value) = value.downcast_ref::<u32>() {
+            Some(Leaf::U32(*value))
+        } else {
+            value.downcast_ref::<i32>().map(|value| Leaf::I32(*value))
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Leaf::F32(value) => format!("{:.3}", value),
+            Leaf::F64(value) => format!("{:.3}", value),
+            Leaf::Bool(value) => value.to_string(),
+            Leaf::Usize(value) => value.to_string(),
+            Leaf::U32(value) => value.to_string(),
+            Leaf::I32(value) => value.to_string(),
+            Leaf::Euler(degrees) => format!("{:.1}°", degrees),
+        }
+    }
+}
+
+// 10% of the value but at least a centimeter, so both positions and light intensities move
+fn float_step(value: f64, coarse: bool) -> f64 {
+    let step = (value.abs() * 0.1).max(0.01);
+    if coarse { step * 10.0 } else { step }
+}
+
+fn collect_leaves(value: &dyn PartialReflect, path: String, depth: usize, out: &mut Vec<(String, Leaf)>) {
+    if let Some(rotation) = value.try_downcast_ref::<Quat>() {
+        let (yaw, pitch, roll) = rotation.to_euler(EulerRot::YXZ);
+        for (name, angle) in [("yaw", yaw), ("pitch", pitch), ("roll", roll)] {
+            // the axis follows the path to the quaternion
+            out.push((format!("{}:{}", path, name), Leaf::Euler(angle.to_degrees())));
+        }
+        return;
+    }
+    if let Some(leaf) = Leaf::read(value) {
+        out.push((path, leaf));
+        return;
+    }
+    if depth >= MAX_DEPTH {
+        return;
+    }
+    match value.reflect_ref() {
+        ReflectRef::Struct(value) => {
+            for index in 0..value.field_len() {
+                if let (Some(name), Some(field)) = (value.name_at(index), value.field_at(index)) {
+                    collect_leaves(field, format!("{}.{}", path, name), depth + 1, out);
+                }
+            }
+        }
+        ReflectRef::TupleStruct(value) => {
+            for (index, field) in value.iter_fields().enumerate() {
+                collect_leaves(field, format!("{}.{}", path, index), depth + 1, out);
+            }
+        }
+        ReflectRef::List(value) => {
+            for (index, item) in value.iter().take(MAX_LIST_ITEMS).enumerate() {
+                collect_leaves(item, format!("{}[{}]", path, index), depth + 1, out);
+            }
+        }
+        ReflectRef::Enum(value) => {
+            for (index, field) in value.iter_fields().enumerate() {
+                let name = field.name().map_or(index.to_string(), str::to_string);
+                collect_leaves(field.value(), format!("{}.{}", path, name), depth + 1, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+// one step left or right, true if the value was changed
+fn edit_leaf(root: &mut dyn PartialReflect, path: &str, direction: f32, coarse: bool) -> bool {
+    let (path, axis) = match path.split_once(':') {
+        Some((path, axis)) => (path, Some(axis)),
+        None => (path, None),
+    };
+    let Ok(value) = path.reflect_element_mut(root) else {
+        return false;
+    };
+    let Some(value) = value.try_as_reflect_mut().map(|value| value.as_any_mut()) else {
+        return false;
+    };
+    let sign = direction.signum();
+    if let (Some(rotation), Some(axis)) = (value.downcast_mut::<Quat>(), axis) {
+        let (mut yaw, mut pitch, mut roll) = rotation.to_euler(EulerRot::YXZ);
+        let step = if coarse { 45f32 } else { 5.0 }.to_radians() * sign;
+        match axis {
+            "yaw" => yaw += step,
+            "pitch" => pitch += step,
+            _ => roll += step,
+        }
+        *rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, roll);
+    } else if let Some(value) = value.downcast_mut::<f32>() {
+        *value += float_step(*value as f64, coarse) as f32 * sign;
+    } else if let Some(value) = value.downcast_mut::<f64>() {
+        *value += float_step(*value, coarse) * sign as f64;
+    } else if let Some(value) = value.downcast_mut::<bool>() {
+        *value = !*value;
+    } else {
+        let step = if coarse { 10 } else { 1 };
+        if let Some(value) = value.downcast_mut::<usize>() {
+            *value = if sign > 0.0 { value.saturating_add(step) } else { value.saturating_sub(step) };
+        } else if let Some(value) = value.downcast_mut::<u32>() {
+            *value = if sign > 0.0 { value.saturating_add(step as u32) } else { value.saturating_sub(step as u32) };
+        } else if let Some(value) = value.downcast_mut::<i32>() {
+            *value += step as i32 * sign as i32;
+        } else {
+            return false;
+        }
+    }
+    true
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    // a reflected resource of this crate, e.g. MoveActions
+    Resource(TypeId),
+    Entity(Entity),
+}
+
+#[derive(Resource)]
+pub struct Inspector {
+    pub open: bool,
+    selected: usize,
+    field: usize,
+    toggle_action: Entity,
+    next_entry: Entity,
+    previous_entry: Entity,
+    next_field: Entity,
+    previous_field: Entity,
+    increase: Entity,
+    decrease: Entity,
+    coarse: Entity,
+    node: Option<Entity>,
+    text: Option<Entity>,
+    // the selected entity, marked as Inspected
+    inspected: Option<Entity>,
+}
+
+pub fn setup_inspector(mut commands: Commands) {
+    let set = commands.spawn(ActionSet::new("inspector", "Inspector", 1)).id();
+    let mut action = |name: &'static str, localized: &'static str, key: KeyCode, just_pressed: bool| {
+        let binding = KeyboardBinding::new(key);
+        commands
+            .spawn((
+                Action::new(name, localized, set),
+                KeyboardBindings::new().bind(if just_pressed { binding.just_pressed() } else { binding }),
+                BoolActionValue::new(),
+            ))
+            .id()
+    };
+    let inspector = Inspector {
+        open: false,
+        selected: 0,
+        field: 0,
+        toggle_action: action("toggle_inspector", "Toggle Inspector", KeyCode::F1, true),
+        next_entry: action("inspector_next_entry", "Next Entity", KeyCode::PageDown, true),
+        previous_entry: action("inspector_previous_entry", "Previous Entity", KeyCode::PageUp, true),
+        next_field: action("inspector_next_field", "Next Field", KeyCode::ArrowDown, true),
+        previous_field: action("inspector_previous_field", "Previous Field", KeyCode::ArrowUp, true),
+        increase: action("inspector_increase", "Increase Value", KeyCode::ArrowRight, true),
+        decrease: action("inspector_decrease", "Decrease Value", KeyCode::ArrowLeft, true),
+        // held for bigger steps
+        coarse: action("inspector_coarse", "Coarse Steps", KeyCode::ShiftLeft, false),
+        node: None,
+        text: None,
+        inspected: None,
+    };
+    commands.insert_resource(inspector);
+}
+
+// named scene nodes, lights, cameras and models, without names most entities are meaningless
+type InspectedFilter = Or<(
+    With<Name>,
+    With<SceneRoot>,
+    With<Camera>,
+    With<PointLight>,
+    With<SpotLight>,
+    With<DirectionalLight>,
+)>;
+
+fn entity_label(world: &World, entity: Entity) -> String {
+    match world.get::<Name>(entity) {
+        Some(name) => format!("{} {}", entity, name),
+        None => entity.to_string(),
+    }
+}
+
+fn short_name(registry: &TypeRegistry, type_id: TypeId) -> String {
+    registry
+        .get(type_id)
+        .map_or("unknown".to_string(), |registration| {
+            registration.type_info().type_path_table().short_path().to_string()
+        })
+}
+
+// the reflected components of an entity or the resource, with their editable fields
+fn entry_fields(world: &World, registry: &TypeRegistry, entry: Entry) -> (Vec<String>, Vec<(TypeId, String, Leaf)>) {
+    let mut components = vec![];
+    let mut fields = vec![];
+    let values: Vec<(TypeId, Option<&dyn Reflect>)> = match entry {
+        Entry::Resource(type_id) => {
+            let value = registry
+                .get_type_data::<ReflectResource>(type_id)
+                .and_then(|resource| resource.reflect(world).ok());
+            vec![(type_id, value)]
+        }
+        Entry::Entity(entity) => match world.inspect_entity(entity) {
+            Ok(infos) => infos
+                .filter_map(|info| info.type_id())
+                .map(|type_id| (type_id, world.get_reflect(entity, type_id).ok()))
+                .collect(),
+            Err(_) => vec![],
+        },
+    };
+    for (type_id, value) in values {
+        let name = short_name(registry, type_id);
+        components.push(name.clone());
+        let Some(value) = value else {
+            continue;
+        };
+        let mut leaves = vec![];
+        collect_leaves(value.as_partial_reflect(), String::new(), 0, &mut leaves);
+        fields.extend(leaves.into_iter().map(|(path, leaf)| (type_id, path, leaf)));
+    }
+    (components, fields)
+}
+
+pub fn toggle_inspector(
+    mut commands: Commands,
+    mut inspector: ResMut<Inspector>,
+    bool_value: Query<&BoolActionValue>,
+    mut visibility: Query<&mut Visibility>,
+) {
+    if !bool_value.get(inspector.toggle_action).unwrap().any {
+        return;
+    }
+    inspector.open = !inspector.open;
+    // the animations run again once the inspector is closed
+    if !inspector.open
+        && let Some(entity) = inspector.inspected.take()
+    {
+        commands.entity(entity).try_remove::<Inspected>();
+    }
+    if let Some(mut visibility) = inspector.node.and_then(|node| visibility.get_mut(node).ok()) {
+        *visibility = if inspector.open {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+pub fn inspector_open(inspector: Res<Inspector>) -> bool {
+    inspector.open
+}
+
+// keyboard driven, so it is shown in the desktop window only
+pub fn inspect(
+    world: &mut World,
+    inspected: &mut QueryState<Entity, InspectedFilter>,
+    cameras: &mut QueryState<(Entity, &RenderTarget), With<Camera3d>>,
+) {
+    world.resource_scope(|world, mut inspector: Mut<Inspector>| {
+        let pressed = |world: &World, action: Entity| {
+            world.get::<BoolActionValue>(action).is_some_and(|value| value.any)
+        };
+        if inspector.node.is_none() {
+            // the desktop camera from setup, the xr cameras render into the swapchain
+            let Some(camera) = cameras
+                .iter(world)
+                .find(|(_, target)| matches!(target, RenderTarget::Window(WindowRef::Primary)))
+                .map(|(camera, _)| camera)
+            else {
+                return;
+            };
+            let text = world.spawn(panel_text("", 14.0)).id();
+            let node = world
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        top: px(12),
+                        right: px(12),
+                        width: px(480),
+                        padding: UiRect::all(px(8)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.05, 0.05, 0.08, 0.85)),
+                    UiTargetCamera(camera),
+                ))
+                .add_child(text)
+                .id();
+            inspector.node = Some(node);
+            inspector.text = Some(text);
+        }
+
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let registry = registry.read();
+        let crate_name = concat!(env!("CARGO_CRATE_NAME"), "::");
+        let mut entries: Vec<Entry> = registry
+            .iter_with_data::<ReflectResource>()
+            .filter(|(registration, _)| registration.type_info().type_path().starts_with(crate_name))
+            .map(|(registration, _)| Entry::Resource(registration.type_id()))
+            .collect();
+        let mut entities: Vec<Entity> = inspected.iter(world).collect();
+        entities.sort();
+        entries.extend(entities.into_iter().map(Entry::Entity));
+        if entries.is_empty() {
+            return;
+        }
+
+        let mut selected = inspector.selected.min(entries.len() - 1);
+        if pressed(world, inspector.next_entry) {
+            selected = (selected + 1) % entries.len();
+            inspector.field = 0;
+        }
+        if pressed(world, inspector.previous_entry) {
+            selected = (selected + entries.len() - 1) % entries.len();
+            inspector.field = 0;
+        }
+        inspector.selected = selected;
+        let entry = entries[selected];
+        let selected_entity = match entry {
+            Entry::Entity(entity) => Some(entity),
+            Entry::Resource(_) => None,
+        };
+        if selected_entity != inspector.inspected {
+            if let Some(mut previous) = inspector.inspected.and_then(|entity| world.get_entity_mut(entity).ok()) {
+                previous.remove::<Inspected>();
+            }
+            if let Some(mut entity) = selected_entity.and_then(|entity| world.get_entity_mut(entity).ok()) {
+                entity.insert(Inspected);
+            }
+            inspector.inspected = selected_entity;
+        }
+
+        let (_, fields) = entry_fields(world, &registry, entry);
+        let mut field = inspector.field.min(fields.len().saturating_sub(1));
+        if !fields.is_empty() {
+            if pressed(world, inspector.next_field) {
+                field = (field + 1) % fields.len();
+            }
+            if pressed(world, inspector.previous_field) {
+                field = (field + fields.len() - 1) % fields.len();
+            }
+        }
+        inspector.field = field;
+        let direction = if pressed(world, inspector.increase) {
+            1.0
+        } else if pressed(world, inspector.decrease) {
+            -1.0
+        } else {
+            0.0
+        };
+        if direction != 0.0
+            && let Some((type_id, path, _)) = fields.get(field)
+        {
+            let coarse = pressed(world, inspector.coarse);
+            // through Mut, so change detection sees the edit
+            let edited = match entry {
+                Entry::Resource(type_id) => registry
+                    .get_type_data::<ReflectResource>(type_id)
+                    .and_then(|resource| resource.reflect_mut(&mut *world).ok())
+                    .is_some_and(|mut value| {
+                        edit_leaf(value.as_partial_reflect_mut(), path, direction, coarse)
+                    }),
+                Entry::Entity(entity) => world
+                    .get_reflect_mut(entity, *type_id)
+                    .ok()
+                    .is_some_and(|mut value| {
+                        edit_leaf(value.as_partial_reflect_mut(), path, direction, coarse)
+                    }),
+            };
+            if !edited {
+                warn!("unable to edit {}", path);
+            }
+        }
+
+        // rebuilt after the edit, so the new value is shown
+        let (components, fields) = entry_fields(world, &registry, entry);
+        let mut lines = vec![format!("{} of {} (page up/down)", selected + 1, entries.len())];
+        let first = selected.saturating_sub(LISTED_ENTRIES / 2);
+        for (index, entry) in entries.iter().enumerate().skip(first).take(LISTED_ENTRIES) {
+            let marker = if index == selected { ">" } else { " " };
+            let label = match entry {
+                Entry::Resource(type_id) => format!("resource {}", short_name(&registry, *type_id)),
+                Entry::Entity(entity) => entity_label(world, *entity),
+            };
+            lines.push(format!("{} {}", marker, label));
+        }
+        lines.push(String::new());
+        lines.push(format!("components: {}", components.join(", ")));
+        lines.push("fields (arrows edit, shift for big steps)".to_string());
+        let first = field.saturating_sub(LISTED_FIELDS / 2);
+        for (index, (type_id, path, leaf)) in fields.iter().enumerate().skip(first).take(LISTED_FIELDS) {
+            let marker = if index == field { ">" } else { " " };
+            lines.push(format!(
+                "{} {}{} = {}",
+                marker,
+                short_name(&registry, *type_id),
+                path,
+                leaf.label()
+            ));
+        }
+        let content = lines.join("\n");
+        if let Some(mut text) = inspector.text.and_then(|text| world.get_mut::<Text>(text)) {
+            text.0 = content;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Reflect)]
+    struct Sample {
+        enabled: bool,
+        count: u32,
+        weights: Vec<f32>,
+    }
+
+    #[test]
+    fn float_step_scales_with_value() {
+        assert_eq!(float_step(0.0, false), 0.01);
+        assert!((float_step(5.0, false) - 0.5).abs() < 1e-9);
+        assert!((float_step(5.0, true) - 5.0).abs() < 1e-9);
+        assert!((float_step(-2.0, false) - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn edit_leaf_steps_transform() {
+        let mut transform = Transform::from_xyz(1.0, 0.0, 0.0);
+        assert!(edit_leaf(&mut transform, ".translation.x", 1.0, false));
+        assert!((transform.translation.x - 1.1).abs() < 1e-6);
+        assert!(edit_leaf(&mut transform, ".rotation:yaw", 1.0, false));
+        let (yaw, pitch, roll) = transform.rotation.to_euler(EulerRot::YXZ);
+        assert!((yaw - 5f32.to_radians()).abs() < 1e-5);
+        assert!(pitch.abs() < 1e-5 && roll.abs() < 1e-5);
+    }
+
+    #[test]
+    fn edit_leaf_toggles_and_clamps() {
+        let mut sample = Sample { enabled: false, count: 3, weights: vec![0.5] };
+        assert!(edit_leaf(&mut sample, ".enabled", 1.0, false));
+        assert!(sample.enabled);
+        assert!(edit_leaf(&mut sample, ".count", -1.0, true));
+        assert_eq!(sample.count, 0);
+        assert!(edit_leaf(&mut sample, ".weights[0]", -1.0, false));
+        assert!((sample.weights[0] - 0.45).abs() < 1e-6);
+        assert!(!edit_leaf(&mut sample, ".missing", 1.0, false));
+    }
+}
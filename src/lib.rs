@@ -4,25 +4,77 @@
 // The example includes a simple setup for a Bevy app with OpenXR integration.
 
 mod asset_handler;
+mod camera_settings;
+mod capture;
+mod capabilities;
+mod controllers;
+mod debug_overlay;
+mod environment;
+mod grab;
+mod haptics;
+#[cfg(not(target_os = "android"))]
+mod inspector;
+mod light_editor;
+mod lighting;
+mod mixer;
+mod model_stats;
+mod panel;
+mod passthrough;
+mod passthrough_style;
+mod perf_hud;
+mod pointer;
+mod render_quality;
+mod room_lighting;
+mod settings;
+mod sfx;
+mod shadow_catcher;
+mod skeleton;
+mod spatial_audio;
+#[cfg(not(target_os = "android"))]
+mod spectator;
+mod tools_menu;
+#[cfg(not(target_os = "android"))]
+mod voice_notes;
 use asset_handler::{AssetElement, AssetElementList, ASSET_ELEMENTS, MAX_ASSET_ELEMENTS};
+use capabilities::XrCapabilities;
+use controllers::ControllerDisplay;
+use debug_overlay::DebugOverlay;
+use environment::ActiveEnvironment;
+use grab::{GrabState, ModelTransforms};
+use haptics::{Hand, Haptics, HapticActions};
+use light_editor::LightEditor;
+use lighting::ActiveLighting;
+use passthrough_style::PassthroughStyleMenu;
+use pointer::{AimLeft, AimRight, LaserPointers, PointerHit};
+use room_lighting::RoomLighting;
+use settings::UserSettings;
+use sfx::{Sfx, SfxEvent, SfxPosition};
+use shadow_catcher::ShadowCatcherMaterial;
+use skeleton::SkeletonGizmos;
+use tools_menu::{ToolInput, ToolInputSystems, ToolsMenu};
 // use bevy_audio::AudioPlugin;
 
-use core::f32;
-use std::{f32::consts::FRAC_PI_4, ops::DerefMut};
+use std::f32::consts::FRAC_PI_4;
 
 use bevy_mod_openxr::session::OxrSession;
 
 use bevy::{
-    color::palettes::css::{self, WHITE}, light::{CascadeShadowConfigBuilder, DirectionalLight}, prelude::*, render::view::NoIndirectDrawing, scene::SceneInstanceReady
+    diagnostic::{EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin, SystemInformationDiagnosticsPlugin},
+    log::{Level, LogPlugin},
+    prelude::*,
+    render::diagnostic::RenderDiagnosticsPlugin,
+    scene::SceneInstanceReady,
 };
 use bevy_mod_openxr::{
+    action_binding::OxrSendActionBindings,
+    action_set_syncing::OxrActionSetSyncSet,
     add_xr_plugins,
-    exts::OxrExtensions,
+    features::handtracking::HandTrackingPlugin,
     init::OxrInitPlugin,
+    resources::OxrInstance,
 };
 
 use bevy_mod_xr::session::{XrSessionCreated, XrTrackingRoot};
-use bevy_mod_xr::camera::XrProjection;
 use bevy_xr_utils::transform_utils::{self};
 use bevy::prelude::MorphWeights;
 use schminput::prelude::*;
@@ -38,17 +90,20 @@ struct CoreActions {
     set: Entity,
     left_pose: Entity,
     right_pose: Entity,
+    left_aim: Entity,
+    right_aim: Entity,
 }
 
 #[allow(dead_code)]
-#[derive(Resource, Clone, Copy)]
+#[derive(Resource, Clone, Copy, Reflect)]
+#[reflect(Resource)]
 struct MoveActions {
     set: Entity,
     move_action: Entity,
     turn_action: Entity,
     look: Entity,
     new_scene: Entity,
-    center_camera: Entity,
+    toggle_gizmos: Entity,
     move_left: Entity,
     move_right: Entity,
     move_forward: Entity,
@@ -57,10 +112,12 @@ struct MoveActions {
     move_down: Entity,
     shown_scene: usize,
     new_scene_released: bool,
+    toggle_gizmos_released: bool,
 }
 
 // Zustand für Turn-steuerung
-#[derive(Resource, Default)]
+#[derive(Resource, Default, Reflect)]
+#[reflect(Resource)]
 struct TurnState {
     ready: bool,
 }
@@ -72,76 +129,170 @@ struct AnimationToPlay {
     index: AnimationNodeIndex,
 }
 
-#[derive(Component)]
-struct MyMusic;
-
-pub const HP_MIXED_REALITY_PROFILE: &str = "/interaction_profiles/htc/mixed_reality_controller";
+pub const HP_MIXED_REALITY_PROFILE: &str = "/interaction_profiles/hp/mixed_reality_controller";
 
 #[bevy_main]
 fn main() {
-    App::new()
-        .add_plugins(add_xr_plugins(DefaultPlugins).set(OxrInitPlugin {
-            exts: {
-                let mut exts = OxrExtensions::default();
-                exts.enable_fb_passthrough();
-                exts.ext_hp_mixed_reality_controller = true;
-                exts
-            },
-            ..default()
-        }))
-        .add_plugins(bevy_mod_openxr::features::fb_passthrough::OxrFbPassthroughPlugin)
+    let capabilities = XrCapabilities::probe();
+    let settings = UserSettings::load();
+    let mut app = App::new();
+    let mut plugins = add_xr_plugins(DefaultPlugins).set(OxrInitPlugin {
+        exts: capabilities.extensions(),
+        resolutions: render_quality::swapchain_resolutions(&settings),
+        ..default()
+    }).set(LogPlugin {
+        // e.g. "debug" for the per frame movement logs
+        level: settings.get("log.level").unwrap_or(Level::INFO),
+        ..default()
+    });
+    // the plugin would try to create hand trackers without the extension and fail
+    if !capabilities.hand_tracking {
+        plugins = plugins.disable::<HandTrackingPlugin>();
+    }
+    app.add_plugins(plugins);
+    if capabilities.passthrough {
+        app.add_plugins(bevy_mod_openxr::features::fb_passthrough::OxrFbPassthroughPlugin);
+    }
+    // microphone capture uses cpal, which is only a dependency on the desktop,
+    // a second window and the keyboard driven inspector only exist on the desktop too
+    #[cfg(not(target_os = "android"))]
+    app
+        .insert_non_send_resource(voice_notes::Recorder::default())
+        .add_systems(Startup, voice_notes::setup_voice_notes)
+        .add_systems(Update, voice_notes::record_voice_note.after(pointer::cast_laser_pointers).after(light_editor::toggle_light_editor))
+        .add_observer(voice_notes::restore_voice_notes)
+        .add_systems(Startup, spectator::setup_spectator)
+        .add_systems(Update, spectator::cycle_spectator)
+        .add_systems(Update, spectator::manage_spectator_window.after(spectator::cycle_spectator))
+        .add_systems(PostUpdate, spectator::follow_player.before(TransformSystems::Propagate))
+        .add_systems(Startup, inspector::setup_inspector)
+        .add_systems(Update, inspector::toggle_inspector)
+        .add_systems(Update, inspector::inspect.after(inspector::toggle_inspector).run_if(inspector::inspector_open));
+    app
         .add_plugins(schminput::DefaultSchminputPlugins)
         .add_plugins(transform_utils::TransformUtilitiesPlugin)
+        .add_plugins(FrameTimeDiagnosticsPlugin::default())
+        .add_plugins(EntityCountDiagnosticsPlugin::default())
+        .add_plugins(SystemInformationDiagnosticsPlugin)
+        .add_plugins(RenderDiagnosticsPlugin)
+        .add_plugins(MaterialPlugin::<ShadowCatcherMaterial>::default())
+        .init_gizmo_group::<SkeletonGizmos>()
         .add_systems(PreStartup, setup_assets)
         .add_systems(Startup, setup_mesh_and_animation)
         .add_systems(Startup, setup)
         .add_systems(Startup, setup2)
+        .add_systems(Startup, camera_settings::setup_camera_settings)
+        .add_systems(Startup, render_quality::setup_render_quality)
+        .add_systems(Startup, capture::setup_capture)
+        .add_systems(Startup, perf_hud::setup_perf_hud)
+        .add_systems(Startup, skeleton::setup_skeleton_view)
+        .add_systems(Startup, model_stats::setup_model_stats)
+        .add_systems(Startup, grab::setup_grab_actions)
+        .add_systems(Startup, passthrough::setup_passthrough)
+        .add_systems(Startup, passthrough_style::setup_passthrough_style)
+        .add_systems(Startup, environment::setup_environment)
+        .add_systems(Startup, lighting::setup_lighting)
+        .add_systems(Startup, light_editor::setup_light_editor)
+        .add_systems(Startup, room_lighting::setup_room_lighting)
+        .add_systems(Startup, shadow_catcher::setup_shadow_catcher)
+        .add_systems(Startup, spatial_audio::setup_listener)
+        .add_systems(Startup, mixer::setup_mixer)
+        .add_systems(Startup, sfx::setup_sfx_library)
+        .add_systems(Startup, tools_menu::setup_tools_menu)
+        .add_systems(Startup, capabilities::log_capabilities)
+        .add_systems(Startup, haptics::create_haptic_actions.run_if(resource_exists::<OxrInstance>))
+        .add_systems(OxrSendActionBindings, haptics::suggest_haptic_bindings.run_if(resource_exists::<HapticActions>))
+        .add_systems(XrSessionCreated, haptics::attach_haptic_set.run_if(resource_exists::<HapticActions>))
+        .add_systems(PreUpdate, haptics::sync_haptic_set.run_if(resource_exists::<HapticActions>).before(OxrActionSetSyncSet))
         .add_systems(XrSessionCreated, create_view_space)
-        .add_systems(Update, modify_cams)
-        .add_systems(Update, adjust_near_plane)
+        .add_systems(XrSessionCreated, shadow_catcher::create_floor_space)
+        .add_systems(XrSessionCreated, render_quality::store_recommended_resolution)
+        .add_systems(Update, render_quality::cycle_render_quality.in_set(ToolInputSystems))
+        .add_systems(Update, render_quality::apply_render_quality.after(render_quality::cycle_render_quality))
+        .add_systems(Update, capture::capture.in_set(ToolInputSystems))
+        .add_systems(Update, camera_settings::apply_camera_settings)
         .add_systems(Update, update_morph_targets)
         .add_systems(Update, run)
         .add_systems(Update, snap_turn_system)
-        .add_systems(Update, animate_light_direction)
         .add_systems(Update, spawn_new_scene)
+        .add_systems(Update, toggle_gizmos)
+        .add_systems(Update, controllers::update_controller_models)
+        .add_systems(Update, controllers::show_controller_models.after(toggle_gizmos))
+        .add_systems(Update, debug_overlay::draw_debug_overlay.after(run).after(snap_turn_system))
+        .add_systems(Update, perf_hud::toggle_perf_hud.in_set(ToolInputSystems))
+        .add_systems(Update, perf_hud::place_perf_hud.after(perf_hud::toggle_perf_hud).after(run).after(snap_turn_system))
+        .add_systems(Update, perf_hud::update_perf_hud.after(perf_hud::place_perf_hud))
+        .add_systems(Update, skeleton::control_skeleton_view.in_set(ToolInputSystems))
+        .add_systems(Update, skeleton::draw_skeleton.after(skeleton::control_skeleton_view).after(pointer::cast_laser_pointers))
+        .add_systems(Update, skeleton::update_skeleton_panel.after(skeleton::draw_skeleton))
+        .add_systems(Update, skeleton::update_joint_label.after(skeleton::draw_skeleton).after(run).after(snap_turn_system))
+        .add_systems(Update, model_stats::toggle_model_stats.in_set(ToolInputSystems))
+        .add_systems(Update, model_stats::update_model_stats.after(model_stats::toggle_model_stats))
+        .add_observer(model_stats::collect_model_stats)
+        .add_systems(Update, debug_overlay::update_debug_panel.after(debug_overlay::draw_debug_overlay).after(toggle_gizmos))
+        .add_systems(Update, pointer::cast_laser_pointers.after(run))
+        .add_systems(Update, grab::grab_model.after(pointer::cast_laser_pointers).before(spawn_new_scene))
+        .add_systems(Update, grab::reset_model.after(grab::grab_model))
+        .add_systems(Update, passthrough::toggle_passthrough)
+        .add_systems(Update, passthrough::apply_passthrough.after(passthrough::toggle_passthrough))
+        .add_systems(Update, passthrough_style::toggle_passthrough_style_menu)
+        .add_systems(Update, passthrough_style::edit_passthrough_style.after(passthrough_style::toggle_passthrough_style_menu))
+        .add_systems(Update, passthrough_style::apply_passthrough_style.after(passthrough_style::edit_passthrough_style))
+        .add_systems(Update, environment::cycle_environment)
+        .add_systems(Update, environment::select_environment.after(environment::cycle_environment).after(passthrough::toggle_passthrough).after(spawn_new_scene))
+        .add_systems(Update, environment::apply_environment.after(environment::select_environment))
+        .add_systems(Update, environment::draw_environment_grid.after(environment::select_environment))
+        .add_systems(Update, tools_menu::toggle_tools_menu)
+        .add_systems(Update, tools_menu::navigate_tools_menu.after(tools_menu::toggle_tools_menu))
+        // every system reading ToolInput, the menu labels show their result in the same frame
+        .configure_sets(Update, ToolInputSystems.after(tools_menu::navigate_tools_menu))
+        .add_systems(Update, tools_menu::update_tools_menu.after(ToolInputSystems))
+        .add_systems(Update, lighting::control_lighting.in_set(ToolInputSystems))
+        .add_systems(Update, lighting::select_lighting.after(environment::select_environment).after(lighting::control_lighting).after(room_lighting::toggle_room_lighting))
+        .add_systems(Update, lighting::apply_lighting.after(lighting::select_lighting))
+        .add_systems(Update, lighting::rotate_sun.after(lighting::apply_lighting))
+        .add_systems(Update, light_editor::toggle_light_editor)
+        .add_systems(Update, light_editor::edit_main_light.after(light_editor::toggle_light_editor).after(lighting::rotate_sun))
+        .add_systems(Update, light_editor::place_point_light.after(light_editor::toggle_light_editor))
+        .add_systems(Update, room_lighting::toggle_room_lighting.in_set(ToolInputSystems))
+        .add_systems(Update, room_lighting::match_room_light.after(light_editor::toggle_light_editor))
+        .add_systems(Update, room_lighting::apply_room_lighting.after(lighting::apply_lighting).after(room_lighting::match_room_light))
+        .add_systems(Update, shadow_catcher::update_shadow_catcher.after(grab::reset_model).after(passthrough::toggle_passthrough))
+        .add_systems(PostUpdate, spatial_audio::follow_headset.before(TransformSystems::Propagate))
+        .add_observer(spatial_audio::attach_model_sounds)
+        .add_systems(Update, mixer::control_mixer.in_set(ToolInputSystems))
+        .add_systems(Update, mixer::play_scene_music.after(spawn_new_scene))
+        .add_systems(Update, sfx::play_sfx.after(snap_turn_system).after(spawn_new_scene).after(grab::reset_model).before(mixer::apply_mixer))
+        .add_systems(Update, mixer::apply_mixer.after(mixer::control_mixer).after(mixer::play_scene_music))
+        .add_systems(Last, settings::save_settings)
+        .add_systems(Update, haptics::play_haptics.after(snap_turn_system).after(spawn_new_scene))
+        .add_message::<Haptics>()
+        .add_message::<PointerHit>()
+        .add_message::<Sfx>()
+        .add_message::<ToolInput>()
         .insert_resource(ClearColor(Color::NONE))
         .insert_resource(TurnState::default())
+        .register_type::<MoveActions>()
+        .register_type::<TurnState>()
+        .insert_resource(ControllerDisplay::default())
+        .insert_resource(DebugOverlay::default())
+        .insert_resource(ActiveEnvironment::default())
+        .insert_resource(ActiveLighting::default())
+        .insert_resource(LaserPointers::default())
+        .insert_resource(GrabState::default())
+        .insert_resource(ModelTransforms::default())
+        .insert_resource(capabilities)
+        .insert_resource(settings)
         // .init_asset::<AudioSource>()
         .run();
 }
 
 #[derive(Component)]
-struct CamModified;
-
-fn modify_cams(cams: Query<Entity, (With<Camera>, Without<CamModified>)>, mut commands: Commands) {
-    for cam in &cams {
-        commands.entity(cam)
-        .insert(Msaa::Off)
-        .insert(NoIndirectDrawing)
-        .insert(CamModified);
-    }
-}
-
-fn adjust_near_plane(query: Query<&mut Projection, With<Camera3d>>) {
-    // Safely get the single camera projection if present and adjust its near plane when it's perspective.
-    for mut projection in query {
-        match projection.deref_mut() {
-            Projection::Perspective(perspective_projection) => perspective_projection.near = 0.003,
-            Projection::Orthographic(orthographic_projection) => orthographic_projection.near = 0.003,
-            Projection::Custom(custom_projection) => {
-                if let Some(xr) = custom_projection.get_mut::<XrProjection>() {
-                    xr.near = 0.1;
-                } else {
-                    error_once!("unknown custom camera projection");
-                }
-            }
-        }
-    }
-    
-}
+struct HeadsetView;
 
+// the entity selected in the inspector, its morph target animation pauses so the weights can be edited
 #[derive(Component)]
-struct HeadsetView;
+pub struct Inspected;
 
 fn create_view_space(
     session: Res<OxrSession>, 
@@ -167,11 +318,7 @@ fn setup_mesh_and_animation(
     asset_elements: Res<AssetElementList>,
     asset_server: Res<AssetServer>,
     mut graphs: ResMut<Assets<AnimationGraph>>,
-    // mut meshes: ResMut<Assets<Mesh>>,
-    // mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    // let gap = 4.0; // space between two ears
-
     if let Some(handle) = asset_elements.get_by_index(0) {
         let (graph, index) = AnimationGraph::from_clip(
             asset_server.load(GltfAssetLabel::Animation(0).from_asset(ASSET_ELEMENTS[0].file_name)),
@@ -187,113 +334,38 @@ fn setup_mesh_and_animation(
         let _entity = commands.spawn((
             animation_to_play,
             mesh_scene,
-            // Emitter::default(),
-            // bevy_audio::AudioPlayer::new(asset_server.load("laser.wav")),
-            // bevy_audio::PlaybackSettings::LOOP.with_spatial(true),
         )).observe(play_animation_when_ready).id();
     }
-    // let listener = bevy_audio::SpatialListener::new(gap);
-    // commands.spawn((
-    //     Transform::default(),
-    //     Visibility::default(),
-    //     listener.clone(),
-    //     children![
-    //         // left ear indicator
-    //         (
-    //             Mesh3d(meshes.add(Cuboid::new(0.2, 0.2, 0.2))),
-    //             MeshMaterial3d(materials.add(Color::from(RED))),
-    //             Transform::from_translation(listener.left_ear_offset),
-    //         ),
-    //         // right ear indicator
-    //         (
-    //             Mesh3d(meshes.add(Cuboid::new(0.2, 0.2, 0.2))),
-    //             MeshMaterial3d(materials.add(Color::from(LIME))),
-    //             Transform::from_translation(listener.right_ear_offset),
-    //         )
-    //     ],
-    // ));
 }
 
-// fn update_positions(
-//     time: Res<Time>,
-//     mut emitters: Query<(&mut Transform, &mut Emitter), With<Emitter>>,
-//     keyboard: Res<ButtonInput<KeyCode>>,
-// ) {
-//     for (mut emitter_transform, mut emitter) in emitters.iter_mut() {
-//         if keyboard.just_pressed(KeyCode::Space) {
-//             if emitter.stopwatch.is_paused() {
-//                 emitter.stopwatch.unpause();
-//             } else {
-//                 emitter.stopwatch.pause();
-//             }
-//         }
-
-//         emitter.stopwatch.tick(time.delta());
-
-//         if !emitter.stopwatch.is_paused() {
-//             emitter_transform.translation.x = ops::sin(emitter.stopwatch.elapsed_secs()) * 3.0;
-//             emitter_transform.translation.z = ops::cos(emitter.stopwatch.elapsed_secs()) * 3.0;
-//         }
-//     }
-// }
-
-// fn update_listener(
-//     keyboard: Res<ButtonInput<KeyCode>>,
-//     time: Res<Time>,
-//     mut listeners: Single<&mut Transform, With<bevy_audio::SpatialListener>>,
-// ) {
-//     let speed = 2.;
-
-//     if keyboard.pressed(KeyCode::ArrowRight) {
-//         listeners.translation.x += speed * time.delta_secs();
-//     }
-//     if keyboard.pressed(KeyCode::ArrowLeft) {
-//         listeners.translation.x -= speed * time.delta_secs();
-//     }
-//     if keyboard.pressed(KeyCode::ArrowDown) {
-//         listeners.translation.z += speed * time.delta_secs();
-//     }
-//     if keyboard.pressed(KeyCode::ArrowUp) {
-//         listeners.translation.z -= speed * time.delta_secs();
-//     }
-// }
-
-fn setup2(mut cmds: Commands) {
+fn setup2(mut cmds: Commands, capabilities: Res<XrCapabilities>) {
     let player_set = cmds.spawn(ActionSet::new("player", "Player", 1)).id();
     let pose_set = cmds.spawn(ActionSet::new("pose", "Poses", 0)).id();
     let move_action = cmds
         .spawn((
             Action::new("move", "Move", player_set),
-            OxrBindings::new()
-                .bindings(OCULUS_TOUCH_PROFILE, ["/user/hand/left/input/thumbstick"])
-                .bindings(HP_MIXED_REALITY_PROFILE, ["/user/hand/left/input/thumbstick"]),
+            capabilities.bindings("/user/hand/left/input/thumbstick"),
             Vec2ActionValue::new(),
         ))
         .id();
     let turn_action = cmds
         .spawn((
             Action::new("turn", "Turn", player_set),
-            OxrBindings::new()
-                .bindings(OCULUS_TOUCH_PROFILE, ["/user/hand/right/input/thumbstick"])
-                .bindings(HP_MIXED_REALITY_PROFILE, ["/user/hand/right/input/thumbstick"]),
+            capabilities.bindings("/user/hand/right/input/thumbstick"),
             Vec2ActionValue::new(),
         ))
         .id();
     let look = cmds
         .spawn((
             Action::new("look", "Look", player_set),
-            OxrBindings::new()
-                .bindings(OCULUS_TOUCH_PROFILE,["/user/hand/right/input/thumbstick/x"])
-                .bindings(HP_MIXED_REALITY_PROFILE,["/user/hand/right/input/thumbstick/x"]),
+            capabilities.bindings("/user/hand/right/input/thumbstick/x"),
             F32ActionValue::new(),
         ))
         .id();
     let new_scene = cmds
         .spawn((
             Action::new("new_scene", "New scene", player_set),
-            OxrBindings::new()
-                .bindings(OCULUS_TOUCH_PROFILE, ["/user/hand/right/input/a/click"])
-                .bindings(HP_MIXED_REALITY_PROFILE, ["/user/hand/right/input/a/click"]),
+            capabilities.bindings("/user/hand/right/input/a/click"),
             KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyI)),
             GamepadBindings::new()
                 .bind(GamepadBinding::new(GamepadBindingSource::South).button_just_pressed()),
@@ -342,25 +414,20 @@ fn setup2(mut cmds: Commands) {
             BoolActionValue::new(),
         ))
         .id();
-    let center_camera = cmds
+    let toggle_gizmos = cmds
         .spawn((
-            Action::new("center_camera", "Center Camera", player_set),
-            OxrBindings::new()
-                .bindings(OCULUS_TOUCH_PROFILE, ["/user/hand/left/input/y/click"])
-                .bindings(HP_MIXED_REALITY_PROFILE, ["/user/hand/left/input/y/click"]),
-            GamepadBindings::new()
-                .bind(GamepadBinding::new(GamepadBindingSource::East).button_just_pressed()),
+            Action::new("toggle_gizmos", "Toggle Debug Overlay", player_set),
+            capabilities.bindings("/user/hand/left/input/thumbstick/click"),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyG)),
             BoolActionValue::new(),
         ))
         .id();
-    let left_hand = cmds.spawn(HandLeft).id();
-    let right_hand = cmds.spawn(HandRight).id();
+    let left_hand = cmds.spawn((HandLeft, Transform::default(), Visibility::default())).id();
+    let right_hand = cmds.spawn((HandRight, Transform::default(), Visibility::default())).id();
     let left_pose = cmds
         .spawn((
             Action::new("hand_left_pose", "Left Hand Pose", pose_set),
-            OxrBindings::new()
-                .bindings(OCULUS_TOUCH_PROFILE, ["/user/hand/left/input/grip/pose"])
-                .bindings(HP_MIXED_REALITY_PROFILE, ["/user/hand/left/input/grip/pose"]),
+            capabilities.bindings("/user/hand/left/input/grip/pose"),
             AttachSpaceToEntity(left_hand),
             SpaceActionValue::new(),
         ))
@@ -368,20 +435,36 @@ fn setup2(mut cmds: Commands) {
     let right_pose = cmds
         .spawn((
             Action::new("hand_right_pose", "Right Hand Pose", pose_set),
-            OxrBindings::new()
-                .bindings(OCULUS_TOUCH_PROFILE, ["/user/hand/right/input/grip/pose"])
-                .bindings(HP_MIXED_REALITY_PROFILE, ["/user/hand/right/input/grip/pose"]),
+            capabilities.bindings("/user/hand/right/input/grip/pose"),
             AttachSpaceToEntity(right_hand),
             SpaceActionValue::new(),
         ))
         .id();
+    let left_aim_entity = cmds.spawn((AimLeft, Transform::default())).id();
+    let right_aim_entity = cmds.spawn((AimRight, Transform::default())).id();
+    let left_aim = cmds
+        .spawn((
+            Action::new("hand_left_aim", "Left Hand Aim", pose_set),
+            capabilities.bindings("/user/hand/left/input/aim/pose"),
+            AttachSpaceToEntity(left_aim_entity),
+            SpaceActionValue::new(),
+        ))
+        .id();
+    let right_aim = cmds
+        .spawn((
+            Action::new("hand_right_aim", "Right Hand Aim", pose_set),
+            capabilities.bindings("/user/hand/right/input/aim/pose"),
+            AttachSpaceToEntity(right_aim_entity),
+            SpaceActionValue::new(),
+        ))
+        .id();
     cmds.insert_resource(MoveActions {
         set: player_set,
         move_action,
         turn_action,
         look,
         new_scene,
-        center_camera,
+        toggle_gizmos,
         move_left,
         move_right,
         move_forward,
@@ -390,56 +473,29 @@ fn setup2(mut cmds: Commands) {
         move_down,
         shown_scene: 0,
         new_scene_released: true,
+        toggle_gizmos_released: true,
     });
     cmds.insert_resource(CoreActions {
         set: pose_set,
         left_pose,
         right_pose,
+        left_aim,
+        right_aim,
     });
 }
 
-fn setup(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-) {
-    commands.spawn((
-        DirectionalLight {
-            shadows_enabled: true,
-            ..default()
-        },
-        // This is a relatively small scene, so use tighter shadow
-        // cascade bounds than the default for better quality.
-        // We also adjusted the shadow map to be larger since we're
-        // only using a single cascade.
-        CascadeShadowConfigBuilder {
-            num_cascades: 1,
-            maximum_distance: 4.0,
-            ..default()
-        }
-        .build(),
-    ));
-    commands.insert_resource(
-        GlobalAmbientLight {
-            color: WHITE.into(),
-            brightness: 400.0,
-            ..default()
-        }
-    );
+fn setup(mut commands: Commands) {
     commands.spawn((
         Camera3d::default(),
         Transform::from_xyz(-2.5, 2.5, 9.0).looking_at(Vec3::ZERO, Vec3::Y),
     ));
-    commands.spawn((
-        AudioPlayer::new(asset_server.load("laser.ogg")),
-        MyMusic,
-    ));
 }
 
 // is called when the app is running
 // this is making the left arm move up and down
 fn update_morph_targets(
     time: Res<Time>,
-    mut query: Query<&mut MorphWeights>,
+    mut query: Query<&mut MorphWeights, Without<Inspected>>,
 ) {
     for mut weights in &mut query {
         let t = time.elapsed_secs();
@@ -452,14 +508,18 @@ fn update_morph_targets(
 
 fn spawn_new_scene(
     mut commands: Commands,
-    query: Query<Entity, With<SceneRoot>>,
+    query: Query<(Entity, &Transform), With<SceneRoot>>,
     assets: Res<AssetElementList>,
+    mut grab_state: ResMut<GrabState>,
+    mut model_transforms: ResMut<ModelTransforms>,
     mut move_actions: ResMut<MoveActions>,
     bool_value: Query<&BoolActionValue>,
+    mut haptics: MessageWriter<Haptics>,
+    mut sfx: MessageWriter<Sfx>,
 ) {
     if !bool_value.get(move_actions.new_scene).unwrap().any {
         if !move_actions.new_scene_released {
-            info!("Button released");
+            debug!("Button released");
         }
         move_actions.new_scene_released = true;
         return;
@@ -469,18 +529,26 @@ fn spawn_new_scene(
     }
     info!("Spawning new scene index {}", move_actions.shown_scene);
     move_actions.new_scene_released = false;
+    haptics.write(Haptics::pulse(Hand::Right));
+    sfx.write(Sfx::new(SfxEvent::SceneSwitch, SfxPosition::Hand(Hand::Right)));
+    // a held model keeps where it was moved to, the new one must not end up in the hand
+    if grab_state.release() {
+        for (_, transform) in query.iter() {
+            model_transforms.set(move_actions.shown_scene, *transform);
+        }
+    }
     move_actions.shown_scene += 1;
     if move_actions.shown_scene >= MAX_ASSET_ELEMENTS {
         move_actions.shown_scene = 0;
     }
-    for entity in query.iter() {
-        info!("despawn: {}", entity.index().to_string());
+    for (entity, _) in query.iter() {
+        debug!("despawn: {}", entity.index().to_string());
         commands.entity(entity).despawn();
     }
     // Function to spawn a new scene if needed
     if let Some(handle) = assets.get_by_index(move_actions.shown_scene) {
         let _entity = commands.spawn((
-            Transform::from_xyz(0.0, 0.0, 0.0),
+            model_transforms.get(move_actions.shown_scene),
             SceneRoot(handle.clone(),
         )
         )).id();
@@ -489,19 +557,24 @@ fn spawn_new_scene(
     }
 }
 
-fn animate_light_direction(
-    time: Res<Time>,
-    mut query: Query<&mut Transform, With<DirectionalLight>>,
+// switches between the controller models and the debug overlay
+fn toggle_gizmos(
+    mut move_actions: ResMut<MoveActions>,
+    bool_value: Query<&BoolActionValue>,
+    mut display: ResMut<ControllerDisplay>,
 ) {
-    for mut transform in &mut query {
-        transform.rotation = Quat::from_euler(
-            EulerRot::ZYX,
-            0.0,
-            time.elapsed_secs() * f32::consts::PI / 5.0,
-            -FRAC_PI_4,
-        );
+    if !bool_value.get(move_actions.toggle_gizmos).unwrap().any {
+        move_actions.toggle_gizmos_released = true;
+        return;
+    }
+    if !move_actions.toggle_gizmos_released {
+        return;
     }
+    move_actions.toggle_gizmos_released = false;
+    display.debug_gizmos = !display.debug_gizmos;
+    info!("debug overlay: {}", display.debug_gizmos);
 }
+
 // is called when the scene is loaded
 // this is where we play the animation (head nodding)
 fn play_animation_when_ready(
@@ -540,38 +613,31 @@ fn play_animation_when_ready(
 fn run(
     move_actions: Res<MoveActions>,
     vec2_value: Query<&Vec2ActionValue>,
-    left_hand: Query<&GlobalTransform, With<HandLeft>>,
     right_hand: Query<&GlobalTransform, With<HandRight>>,
-    mut gizmos: bevy_gizmos::gizmos::Gizmos,
     mut root_query: Query<&mut Transform, With<XrTrackingRoot>>,
+    style_menu: Res<PassthroughStyleMenu>,
+    light_editor: Res<LightEditor>,
+    tools_menu: Res<ToolsMenu>,
+    room_lighting: Res<RoomLighting>,
 ) {
     let movevals = vec2_value.get(move_actions.move_action).unwrap().any;
-    let mut delta = Vec3::ZERO;
-    if movevals.length_squared() > 0.05
+    // the left thumbstick edits the passthrough style, the light or the tools while those are open
+    if !style_menu.open
+        && !light_editor.active
+        && !tools_menu.open
+        && !room_lighting.matching
+        && movevals.length_squared() > 0.05
         && let Ok(mut root_transform) = root_query.single_mut() 
         && let Some(hand) = right_hand.iter().next() {
         let pose = hand.to_isometry();
         
         let forward = pose.rotation.mul_vec3(-Vec3::Z).normalize();
         let right = pose.rotation.mul_vec3(Vec3::X).normalize();
-        info!("forward: {:?}", forward);
-        info!("right: {:?}", right);
-        delta = forward * movevals.y * 0.01 + right * movevals.x * 0.01;
-        root_transform.translation += delta;
+        debug!("forward: {:?}", forward);
+        debug!("right: {:?}", right);
+        root_transform.translation += forward * movevals.y * 0.01 + right * movevals.x * 0.01;
         
     }
-    for hand in left_hand.into_iter() {
-        let mut pose = hand.to_isometry();
-        pose.translation += Vec3A::from(delta);
-        gizmos.arrow(Vec3 { x: 0.0, y: 0.0, z: 0.0 }, pose.rotation.mul_vec3(-Vec3::Z).normalize(), css::BLUE);
-        gizmos.sphere(pose, 0.1, css::BLUE);
-    }
-    for hand in right_hand.into_iter() {
-        let mut pose = hand.to_isometry();
-        pose.translation += Vec3A::from(delta);
-        gizmos.arrow(Vec3 { x: 0.0, y: 0.0, z: 0.0 }, pose.rotation.mul_vec3(-Vec3::Z).normalize(), css::RED);
-        gizmos.sphere(pose, 0.1, css::RED);
-    }
 }
 
 fn snap_turn_system(
@@ -579,7 +645,9 @@ fn snap_turn_system(
     mut root_query: Query<&mut Transform, With<XrTrackingRoot>>,
     vec2_value: Query<&Vec2ActionValue>,
     mut turn_state: ResMut<TurnState>,
-    headset_view_query: Query<&Transform, (With<HeadsetView>, Without<XrTrackingRoot>)>
+    headset_view_query: Query<&Transform, (With<HeadsetView>, Without<XrTrackingRoot>)>,
+    mut haptics: MessageWriter<Haptics>,
+    mut sfx: MessageWriter<Sfx>,
 ) {
     let movevals = vec2_value.get(turn_actions.turn_action).unwrap().any;
     
@@ -596,6 +664,8 @@ fn snap_turn_system(
                 let angle = if turn_value > 0.0 { -FRAC_PI_4 } else { FRAC_PI_4 }; // right = negative Rotation
                 root_transform.rotate_around(world_headset, Quat::from_rotation_y(angle));
                 turn_state.ready = false;
+                haptics.write(Haptics::tick(Hand::Right));
+                sfx.write(Sfx::new(SfxEvent::SnapTurn, SfxPosition::Head));
             } else {
                 info!("No headset view found, cannot rotate.");
             }
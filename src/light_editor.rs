@@ -0,0 +1,241 @@
+use bevy::prelude::*;
+use schminput::prelude::*;
+
+use crate::{
+    capabilities::XrCapabilities,
+    haptics::{Hand, Haptics},
+    lighting::{light_angles, light_rotation, LightingSettings, SceneLight},
+    pointer::AimRight,
+    HandLeft, MoveActions,
+};
+
+// how close the hand has to be to a point light to remove it
+const REMOVE_DISTANCE: f32 = 0.15;
+
+// point lights placed by hand, they survive lighting preset changes
+#[derive(Component)]
+pub struct UserPointLight;
+
+#[derive(Resource)]
+pub struct LightEditor {
+    pub active: bool,
+    // kelvin of the edited main light
+    pub temperature: f32,
+    toggle_action: Entity,
+    aim_action: Entity,
+    place_action: Entity,
+    toggle_released: bool,
+    place_released: bool,
+}
+
+pub fn setup_light_editor(mut cmds: Commands, capabilities: Res<XrCapabilities>) {
+    let set = cmds.spawn(ActionSet::new("light_editor", "Light Editor", 1)).id();
+    let toggle_action = cmds
+        .spawn((
+            Action::new("toggle_light_editor", "Toggle Light Editor", set),
+            capabilities.bindings("/user/hand/left/input/y/click"),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyJ)),
+            BoolActionValue::new(),
+        ))
+        .id();
+    let aim_action = cmds
+        .spawn((
+            Action::new("aim_light", "Aim Light", set),
+            capabilities.bindings("/user/hand/right/input/trigger/value"),
+            F32ActionValue::new(),
+        ))
+        .id();
+    let place_action = cmds
+        .spawn((
+            Action::new("place_light", "Place Point Light", set),
+            capabilities.bindings("/user/hand/left/input/trigger/value"),
+            F32ActionValue::new(),
+        ))
+        .id();
+    cmds.insert_resource(LightEditor {
+        active: false,
+        temperature: 6500.0,
+        toggle_action,
+        aim_action,
+        place_action,
+        toggle_released: true,
+        place_released: true,
+    });
+}
+
+pub fn toggle_light_editor(mut editor: ResMut<LightEditor>, bool_value: Query<&BoolActionValue>) {
+    if !bool_value.get(editor.toggle_action).unwrap().any {
+        editor.toggle_released = true;
+        return;
+    }
+    if !editor.toggle_released {
+        return;
+    }
+    editor.toggle_released = false;
+    editor.active = !editor.active;
+    info!("light editor: {}", editor.active);
+}
+
+// right trigger: the main light shines along the right laser,
+// left thumbstick: up/down intensity, left/right color temperature
+pub fn edit_main_light(
+    mut editor: ResMut<LightEditor>,
+    mut lighting: ResMut<LightingSettings>,
+    move_actions: Res<MoveActions>,
+    vec2_value: Query<&Vec2ActionValue>,
+    f32_value: Query<&F32ActionValue>,
+    aim: Query<&GlobalTransform, With<AimRight>>,
+    time: Res<Time>,
+    mut lights: Query<(&mut SceneLight, &mut DirectionalLight, &mut Transform)>,
+    mut gizmos: Gizmos,
+) {
+    if !editor.active {
+        return;
+    }
+    // the sun, otherwise the shadow casting key light
+    let Some((mut light, mut directional, mut transform)) = lights
+        .iter_mut()
+        .max_by_key(|(light, directional, _)| (light.sun, directional.shadows_enabled))
+    else {
+        return;
+    };
+    if f32_value.get(editor.aim_action).unwrap().any > 0.5
+        && let Ok(aim) = aim.single()
+    {
+        let direction = aim.forward();
+        let (azimuth, elevation) = light_angles(direction);
+        lighting.sun_paused = true;
+        light.azimuth = azimuth - if light.sun { lighting.sun_angle } else { 0.0 };
+        light.elevation = elevation;
+        transform.rotation = light_rotation(azimuth, elevation);
+        let start = aim.translation();
+        gizmos.arrow(start, start + *direction * 0.3, Color::srgb(1.0, 0.9, 0.3));
+    }
+    let stick = vec2_value.get(move_actions.move_action).unwrap().any;
+    if stick.y.abs() > 0.2 {
+        let factor = (stick.y * time.delta_secs() * 2.0).exp();
+        directional.illuminance =
+            (directional.illuminance * factor).clamp(10.0, light_consts::lux::DIRECT_SUNLIGHT);
+    }
+    if stick.x.abs() > 0.2 {
+        editor.temperature =
+            (editor.temperature + stick.x * time.delta_secs() * 3000.0).clamp(1500.0, 12000.0);
+        directional.color = color_temperature(editor.temperature);
+    }
+}
+
+// left trigger: add a point light at the left hand or remove the one next to it
+pub fn place_point_light(
+    mut commands: Commands,
+    mut editor: ResMut<LightEditor>,
+    f32_value: Query<&F32ActionValue>,
+    left_hand: Query<&GlobalTransform, With<HandLeft>>,
+    point_lights: Query<(Entity, &GlobalTransform), With<UserPointLight>>,
+    mut haptics: MessageWriter<Haptics>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !editor.active {
+        return;
+    }
+    if f32_value.get(editor.place_action).unwrap().any < 0.5 {
+        editor.place_released = true;
+        return;
+    }
+    if !editor.place_released {
+        return;
+    }
+    editor.place_released = false;
+    let Ok(hand) = left_hand.single() else {
+        return;
+    };
+    let position = hand.translation();
+    haptics.write(Haptics::tick(Hand::Left));
+    if let Some((entity, _)) = point_lights
+        .iter()
+        .find(|(_, light)| light.translation().distance(position) < REMOVE_DISTANCE)
+    {
+        info!("removing point light");
+        commands.entity(entity).despawn();
+        return;
+    }
+    let color = color_temperature(editor.temperature);
+    info!("placing point light at {:?}", position);
+    commands.spawn((
+        UserPointLight,
+        PointLight {
+            color,
+            intensity: 50_000.0,
+            range: 5.0,
+            ..default()
+        },
+        Transform::from_translation(position),
+        children![(
+            Mesh3d(meshes.add(Sphere::new(0.02))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: color,
+                unlit: true,
+                ..default()
+            })),
+        )],
+    ));
+}
+
+// approximation of the black body color for 1000 K - 40000 K (Tanner Helland)
+pub fn color_temperature(kelvin: f32) -> Color {
+    let t = kelvin / 100.0;
+    let red = if t <= 66.0 {
+        255.0
+    } else {
+        329.699 * (t - 60.0).powf(-0.133_205)
+    };
+    let green = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_16 * (t - 60.0).powf(-0.075_514_85)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+    Color::srgb_u8(
+        red.clamp(0.0, 255.0) as u8,
+        green.clamp(0.0, 255.0) as u8,
+        blue.clamp(0.0, 255.0) as u8,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::color::ColorToPacked;
+
+    use super::*;
+
+    fn rgb(kelvin: f32) -> [u8; 3] {
+        let [red, green, blue, _] = color_temperature(kelvin).to_srgba().to_u8_array();
+        [red, green, blue]
+    }
+
+    #[test]
+    fn daylight_is_white() {
+        assert!(rgb(6500.0).iter().all(|channel| *channel >= 250));
+    }
+
+    #[test]
+    fn low_temperatures_are_warm() {
+        let [red, green, blue] = rgb(2000.0);
+        assert_eq!(red, 255);
+        assert!(red > green && green > blue);
+        assert_eq!(rgb(1500.0)[2], 0);
+    }
+
+    #[test]
+    fn high_temperatures_are_cold() {
+        let [red, green, blue] = rgb(20000.0);
+        assert_eq!(blue, 255);
+        assert!(blue > green && green > red);
+    }
+}
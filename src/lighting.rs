@@ -0,0 +1,390 @@
+use std::f32::consts::{FRAC_PI_4, PI};
+
+use bevy::{light::CascadeShadowConfigBuilder, prelude::*};
+use schminput::prelude::*;
+
+use crate::{
+    environment::{ActiveEnvironment, ENVIRONMENT_PRESETS},
+    passthrough::PassthroughMode,
+    room_lighting::RoomLighting,
+    settings::UserSettings,
+    tools_menu::{Tool, ToolInput},
+};
+
+pub struct PresetLight {
+    pub color: Color,
+    // lux
+    pub illuminance: f32,
+    // radians around Y and down from the horizon
+    pub azimuth: f32,
+    pub elevation: f32,
+    pub shadows: bool,
+    // turned by the sun rotation
+    pub sun: bool,
+}
+
+pub struct ShadowCascades {
+    pub num_cascades: usize,
+    pub first_cascade_far_bound: f32,
+    pub maximum_distance: f32,
+}
+
+pub struct LightingPreset {
+    pub name: &'static str,
+    pub ambient_color: Color,
+    pub ambient_brightness: f32,
+    pub lights: &'static [PresetLight],
+    pub cascades: ShadowCascades,
+    // radians per second, 0.0 keeps the sun still
+    pub sun_rotation_speed: f32,
+}
+
+pub static LIGHTING_PRESETS: &[LightingPreset] = &[
+    LightingPreset {
+        name: "outdoor_sun",
+        ambient_color: Color::WHITE,
+        ambient_brightness: 400.0,
+        lights: &[PresetLight {
+            color: Color::WHITE,
+            illuminance: light_consts::lux::AMBIENT_DAYLIGHT,
+            azimuth: 0.0,
+            elevation: FRAC_PI_4,
+            shadows: true,
+            sun: true,
+        }],
+        // This is a relatively small scene, so use tighter shadow
+        // cascade bounds than the default for better quality.
+        cascades: ShadowCascades {
+            num_cascades: 1,
+            first_cascade_far_bound: 4.0,
+            maximum_distance: 4.0,
+        },
+        sun_rotation_speed: PI / 5.0,
+    },
+    LightingPreset {
+        name: "studio",
+        ambient_color: Color::WHITE,
+        ambient_brightness: 150.0,
+        lights: &[
+            // key
+            PresetLight {
+                color: Color::srgb(1.0, 0.96, 0.9),
+                illuminance: 8000.0,
+                azimuth: -FRAC_PI_4,
+                elevation: 0.6,
+                shadows: true,
+                sun: false,
+            },
+            // fill
+            PresetLight {
+                color: Color::srgb(0.9, 0.95, 1.0),
+                illuminance: 2500.0,
+                azimuth: FRAC_PI_4,
+                elevation: 0.3,
+                shadows: false,
+                sun: false,
+            },
+            // rim
+            PresetLight {
+                color: Color::WHITE,
+                illuminance: 5000.0,
+                azimuth: PI,
+                elevation: 0.8,
+                shadows: false,
+                sun: false,
+            },
+        ],
+        cascades: ShadowCascades {
+            num_cascades: 2,
+            first_cascade_far_bound: 2.0,
+            maximum_distance: 6.0,
+        },
+        sun_rotation_speed: 0.0,
+    },
+    LightingPreset {
+        name: "neutral_review",
+        ambient_color: Color::WHITE,
+        ambient_brightness: 1000.0,
+        lights: &[PresetLight {
+            color: Color::WHITE,
+            illuminance: 3000.0,
+            azimuth: 0.0,
+            elevation: 1.2,
+            shadows: false,
+            sun: false,
+        }],
+        cascades: ShadowCascades {
+            num_cascades: 1,
+            first_cascade_far_bound: 4.0,
+            maximum_distance: 4.0,
+        },
+        sun_rotation_speed: 0.0,
+    },
+    // mixed reality, the light is set from the room estimate, see room_lighting.rs
+    LightingPreset {
+        name: "room",
+        ambient_color: Color::WHITE,
+        ambient_brightness: 400.0,
+        lights: &[PresetLight {
+            color: Color::WHITE,
+            illuminance: 5000.0,
+            azimuth: 0.0,
+            elevation: FRAC_PI_4,
+            shadows: true,
+            sun: false,
+        }],
+        cascades: ShadowCascades {
+            num_cascades: 1,
+            first_cascade_far_bound: 4.0,
+            maximum_distance: 4.0,
+        },
+        sun_rotation_speed: 0.0,
+    },
+];
+
+pub fn lighting_index(name: &str) -> Option<usize> {
+    LIGHTING_PRESETS.iter().position(|preset| preset.name == name)
+}
+
+#[derive(Resource)]
+pub struct LightingSettings {
+    // used when the environment doesn't pick a preset, e.g. in passthrough
+    pub global: usize,
+    // overrides the preset speed when set
+    pub sun_speed: Option<f32>,
+    pub sun_paused: bool,
+    cycle_action: Entity,
+    pause_action: Entity,
+    faster_action: Entity,
+    slower_action: Entity,
+    // how far the sun turned since the preset was applied
+    pub sun_angle: f32,
+}
+
+impl LightingSettings {
+    // radians per second of the sun in the given preset
+    pub fn sun_speed(&self, preset: usize) -> f32 {
+        self.sun_speed.unwrap_or(LIGHTING_PRESETS[preset].sun_rotation_speed)
+    }
+}
+
+#[derive(Resource, Default, PartialEq)]
+pub struct ActiveLighting(pub usize);
+
+// every light spawned from a preset
+#[derive(Component)]
+pub struct SceneLight {
+    pub azimuth: f32,
+    pub elevation: f32,
+    pub sun: bool,
+}
+
+pub fn setup_lighting(mut commands: Commands, settings: Res<UserSettings>) {
+    let set = commands.spawn(ActionSet::new("lighting", "Lighting", 1)).id();
+    let cycle_action = commands
+        .spawn((
+            Action::new("cycle_lighting", "Cycle Lighting", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyK).just_pressed()),
+            GamepadBindings::new()
+                .bind(GamepadBinding::new(GamepadBindingSource::North).button_just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    let pause_action = commands
+        .spawn((
+            Action::new("pause_sun", "Pause Sun Rotation", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyL).just_pressed()),
+            GamepadBindings::new()
+                .bind(GamepadBinding::new(GamepadBindingSource::West).button_just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    let faster_action = commands
+        .spawn((
+            Action::new("sun_faster", "Sun Faster", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::BracketRight).just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    let slower_action = commands
+        .spawn((
+            Action::new("sun_slower", "Sun Slower", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::BracketLeft).just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    let global = settings
+        .get::<String>("lighting")
+        .and_then(|name| lighting_index(&name))
+        .unwrap_or(0);
+    commands.insert_resource(LightingSettings {
+        global,
+        sun_speed: settings.get("lighting.sun_speed"),
+        sun_paused: settings.get("lighting.sun_paused").unwrap_or(false),
+        cycle_action,
+        pause_action,
+        faster_action,
+        slower_action,
+        sun_angle: 0.0,
+    });
+}
+
+// keyboard and gamepad, in the headset through the tools menu
+pub fn control_lighting(
+    mut lighting: ResMut<LightingSettings>,
+    active: Res<ActiveLighting>,
+    bool_value: Query<&BoolActionValue>,
+    mut tool_input: MessageReader<ToolInput>,
+    mut settings: ResMut<UserSettings>,
+) {
+    let pressed = |action: Entity| bool_value.get(action).unwrap().any;
+    let mut cycle = pressed(lighting.cycle_action) as i32;
+    let mut pause = pressed(lighting.pause_action);
+    let mut step = match (pressed(lighting.faster_action), pressed(lighting.slower_action)) {
+        (true, false) => 1,
+        (false, true) => -1,
+        _ => 0,
+    };
+    for input in tool_input.read() {
+        match input.tool {
+            Tool::Lighting => cycle += input.direction,
+            Tool::SunPaused => pause = !pause,
+            Tool::SunSpeed => step += input.direction,
+            _ => {}
+        }
+    }
+    if cycle != 0 {
+        let count = LIGHTING_PRESETS.len() as i32;
+        lighting.global = (lighting.global as i32 + cycle).rem_euclid(count) as usize;
+        let name = LIGHTING_PRESETS[lighting.global].name;
+        info!("lighting: {}", name);
+        settings.set("lighting", name);
+    }
+    if pause {
+        lighting.sun_paused = !lighting.sun_paused;
+        info!("sun rotation paused: {}", lighting.sun_paused);
+        settings.set("lighting.sun_paused", lighting.sun_paused);
+    }
+    if step == 0 {
+        return;
+    }
+    let speed = (lighting.sun_speed(active.0) + step as f32 * PI / 20.0).clamp(-PI, PI);
+    info!("sun rotation speed: {:.2} rad/s", speed);
+    lighting.sun_speed = Some(speed);
+    settings.set("lighting.sun_speed", speed);
+}
+
+// the room estimate in passthrough, then the environment preset, otherwise the global setting
+pub fn select_lighting(
+    environment: Res<ActiveEnvironment>,
+    lighting: Res<LightingSettings>,
+    passthrough: Res<PassthroughMode>,
+    room: Res<RoomLighting>,
+    mut active: ResMut<ActiveLighting>,
+) {
+    let room_preset = (passthrough.enabled && room.enabled).then_some("room");
+    let index = room_preset
+        .or(environment.0.and_then(|index| ENVIRONMENT_PRESETS[index].lighting))
+        .and_then(lighting_index)
+        .unwrap_or(lighting.global);
+    active.set_if_neq(ActiveLighting(index));
+}
+
+pub fn apply_lighting(
+    mut commands: Commands,
+    active: Res<ActiveLighting>,
+    mut lighting: ResMut<LightingSettings>,
+    mut ambient: ResMut<GlobalAmbientLight>,
+    lights: Query<Entity, With<SceneLight>>,
+) {
+    if !active.is_changed() {
+        return;
+    }
+    let preset = &LIGHTING_PRESETS[active.0];
+    info!("applying lighting preset {}", preset.name);
+    for light in &lights {
+        commands.entity(light).despawn();
+    }
+    ambient.color = preset.ambient_color;
+    ambient.brightness = preset.ambient_brightness;
+    lighting.sun_angle = 0.0;
+    for light in preset.lights {
+        commands.spawn((
+            SceneLight {
+                azimuth: light.azimuth,
+                elevation: light.elevation,
+                sun: light.sun,
+            },
+            DirectionalLight {
+                color: light.color,
+                illuminance: light.illuminance,
+                shadows_enabled: light.shadows,
+                ..default()
+            },
+            Transform::from_rotation(light_rotation(light.azimuth, light.elevation)),
+            CascadeShadowConfigBuilder {
+                num_cascades: preset.cascades.num_cascades,
+                first_cascade_far_bound: preset.cascades.first_cascade_far_bound,
+                maximum_distance: preset.cascades.maximum_distance,
+                ..default()
+            }
+            .build(),
+        ));
+    }
+}
+
+pub fn rotate_sun(
+    time: Res<Time>,
+    active: Res<ActiveLighting>,
+    mut lighting: ResMut<LightingSettings>,
+    mut query: Query<(&mut Transform, &SceneLight)>,
+) {
+    if lighting.sun_paused {
+        return;
+    }
+    let speed = lighting.sun_speed(active.0);
+    if speed == 0.0 {
+        return;
+    }
+    lighting.sun_angle += time.delta_secs() * speed;
+    for (mut transform, light) in &mut query {
+        if light.sun {
+            transform.rotation = light_rotation(light.azimuth + lighting.sun_angle, light.elevation);
+        }
+    }
+}
+
+pub fn light_rotation(azimuth: f32, elevation: f32) -> Quat {
+    Quat::from_euler(EulerRot::ZYX, 0.0, azimuth, -elevation)
+}
+
+// inverse of light_rotation for the direction the light shines to,
+// the elevation is kept above the horizon
+pub fn light_angles(direction: Dir3) -> (f32, f32) {
+    let elevation = (-direction.y).asin().max(0.05);
+    let azimuth = (-direction.x).atan2(-direction.z);
+    (azimuth, elevation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn light_angles_invert_light_rotation() {
+        for (azimuth, elevation) in [(0.0, 0.5), (1.2, 0.3), (-2.5, 1.4), (3.0, 0.05)] {
+            let direction = Dir3::new(light_rotation(azimuth, elevation) * Vec3::NEG_Z).unwrap();
+            let (found_azimuth, found_elevation) = light_angles(direction);
+            assert!((found_azimuth - azimuth).abs() < 1e-4, "{} != {}", found_azimuth, azimuth);
+            assert!((found_elevation - elevation).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn light_angles_stay_above_the_horizon() {
+        let (_, elevation) = light_angles(Dir3::Y);
+        assert_eq!(elevation, 0.05);
+        let (_, elevation) = light_angles(Dir3::new(Vec3::new(1.0, 0.2, 0.0)).unwrap());
+        assert_eq!(elevation, 0.05);
+    }
+}
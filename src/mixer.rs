@@ -0,0 +1,264 @@
+use bevy::{audio::Volume, prelude::*};
+use schminput::prelude::*;
+
+use crate::{
+    asset_handler::ASSET_ELEMENTS,
+    settings::UserSettings,
+    tools_menu::{Tool, ToolInput},
+    MoveActions,
+};
+
+// seconds for a cross-fade between the music of two scenes
+const FADE_TIME: f32 = 2.0;
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bus {
+    Master,
+    Music,
+    Effects,
+}
+
+impl Bus {
+    pub fn name(self) -> &'static str {
+        match self {
+            Bus::Master => "master",
+            Bus::Music => "music",
+            Bus::Effects => "effects",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Bus::Master => Bus::Music,
+            Bus::Music => Bus::Effects,
+            Bus::Effects => Bus::Master,
+        }
+    }
+}
+
+// master, music and effects volume, 0.0 - 1.0, stored in the user settings
+#[derive(Resource)]
+pub struct AudioMixer {
+    pub muted: bool,
+    pub master: f32,
+    pub music: f32,
+    pub effects: f32,
+    // the bus the volume keys change
+    pub selected: Bus,
+    mute_action: Entity,
+    bus_action: Entity,
+    louder_action: Entity,
+    quieter_action: Entity,
+}
+
+impl AudioMixer {
+    pub fn volume(&self, bus: Bus) -> f32 {
+        match bus {
+            Bus::Master => self.master,
+            Bus::Music => self.music,
+            Bus::Effects => self.effects,
+        }
+    }
+
+    fn volume_mut(&mut self, bus: Bus) -> &mut f32 {
+        match bus {
+            Bus::Master => &mut self.master,
+            Bus::Music => &mut self.music,
+            Bus::Effects => &mut self.effects,
+        }
+    }
+
+    fn gain(&self, level: f32) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.master * level
+        }
+    }
+}
+
+// background track of a catalog entry
+#[derive(Component)]
+pub struct MusicTrack {
+    scene: usize,
+    track: usize,
+    // 0.0 - 1.0, faded in when started and out when the scene changes
+    level: f32,
+    fading_out: bool,
+}
+
+pub fn setup_mixer(mut commands: Commands, settings: Res<UserSettings>) {
+    let set = commands.spawn(ActionSet::new("audio", "Audio", 1)).id();
+    let mute_action = commands
+        .spawn((
+            Action::new("mute", "Mute", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyU).just_pressed()),
+            GamepadBindings::new()
+                .bind(GamepadBinding::new(GamepadBindingSource::Select).button_just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    let bus_action = commands
+        .spawn((
+            Action::new("select_bus", "Select Volume", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyY).just_pressed()),
+            GamepadBindings::new()
+                .bind(GamepadBinding::new(GamepadBindingSource::East).button_just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    let louder_action = commands
+        .spawn((
+            Action::new("volume_up", "Volume Up", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::Equal).just_pressed()),
+            GamepadBindings::new()
+                .bind(GamepadBinding::new(GamepadBindingSource::DPadUp).button_just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    let quieter_action = commands
+        .spawn((
+            Action::new("volume_down", "Volume Down", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::Minus).just_pressed()),
+            GamepadBindings::new()
+                .bind(GamepadBinding::new(GamepadBindingSource::DPadDown).button_just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    commands.insert_resource(AudioMixer {
+        muted: settings.get("audio.muted").unwrap_or(false),
+        master: settings.get("audio.master").unwrap_or(1.0),
+        music: settings.get("audio.music").unwrap_or(0.6),
+        effects: settings.get("audio.effects").unwrap_or(1.0),
+        selected: Bus::Master,
+        mute_action,
+        bus_action,
+        louder_action,
+        quieter_action,
+    });
+}
+
+// keyboard and gamepad change the selected bus, the tools menu has an entry per bus
+pub fn control_mixer(
+    mut mixer: ResMut<AudioMixer>,
+    bool_value: Query<&BoolActionValue>,
+    mut tool_input: MessageReader<ToolInput>,
+    mut settings: ResMut<UserSettings>,
+) {
+    let pressed = |action: Entity| bool_value.get(action).unwrap().any;
+    let mut mute = pressed(mixer.mute_action);
+    if pressed(mixer.bus_action) {
+        mixer.selected = mixer.selected.next();
+        info!("volume keys change {}", mixer.selected.name());
+    }
+    let mut steps = vec![];
+    match (pressed(mixer.louder_action), pressed(mixer.quieter_action)) {
+        (true, false) => steps.push((mixer.selected, 1)),
+        (false, true) => steps.push((mixer.selected, -1)),
+        _ => {}
+    }
+    for input in tool_input.read() {
+        match input.tool {
+            Tool::Mute => mute = !mute,
+            Tool::MasterVolume => steps.push((Bus::Master, input.direction)),
+            Tool::MusicVolume => steps.push((Bus::Music, input.direction)),
+            Tool::EffectsVolume => steps.push((Bus::Effects, input.direction)),
+            _ => {}
+        }
+    }
+    if mute {
+        mixer.muted = !mixer.muted;
+        info!("audio muted: {}", mixer.muted);
+        settings.set("audio.muted", mixer.muted);
+    }
+    for (bus, direction) in steps {
+        let volume = mixer.volume_mut(bus);
+        *volume = (*volume + direction as f32 * VOLUME_STEP).clamp(0.0, 1.0);
+        let volume = *volume;
+        info!("{} volume: {:.1}", bus.name(), volume);
+        settings.set(&format!("audio.{}", bus.name()), volume);
+    }
+}
+
+// plays the playlist of the shown catalog entry and cross-fades when it changes
+pub fn play_scene_music(
+    mut commands: Commands,
+    move_actions: Res<MoveActions>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    mut tracks: Query<(Entity, &mut MusicTrack, Option<&AudioSink>)>,
+) {
+    let scene = move_actions.shown_scene;
+    let playlist = ASSET_ELEMENTS.get(scene).map_or(&[][..], |asset| asset.music);
+    let step = time.delta_secs() / FADE_TIME;
+    let mut playing = false;
+    for (entity, mut track, sink) in &mut tracks {
+        if track.scene != scene {
+            track.fading_out = true;
+        }
+        if track.fading_out {
+            track.level -= step;
+            if track.level <= 0.0 {
+                commands.entity(entity).despawn();
+            }
+            continue;
+        }
+        playing = true;
+        track.level = (track.level + step).min(1.0);
+        // the sink is added once the track is loaded
+        if sink.is_some_and(|sink| sink.empty()) {
+            commands.entity(entity).despawn();
+            let next = (track.track + 1) % playlist.len();
+            spawn_track(&mut commands, &asset_server, playlist, scene, next, 1.0);
+        }
+    }
+    if !playing && !playlist.is_empty() {
+        spawn_track(&mut commands, &asset_server, playlist, scene, 0, 0.0);
+    }
+}
+
+fn spawn_track(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    playlist: &[&'static str],
+    scene: usize,
+    track: usize,
+    level: f32,
+) {
+    info!("playing music {}", playlist[track]);
+    commands.spawn((
+        MusicTrack {
+            scene,
+            track,
+            level,
+            fading_out: false,
+        },
+        AudioPlayer::new(asset_server.load(playlist[track])),
+        PlaybackSettings::ONCE,
+    ));
+}
+
+// sets the volume of every playing sound, music tracks are scaled by their fade level
+pub fn apply_mixer(
+    mixer: Res<AudioMixer>,
+    mut global_volume: ResMut<GlobalVolume>,
+    mut music: Query<(&PlaybackSettings, &MusicTrack, &mut AudioSink)>,
+    mut effects: Query<(&PlaybackSettings, &mut AudioSink), Without<MusicTrack>>,
+    mut spatial_effects: Query<(&PlaybackSettings, &mut SpatialAudioSink)>,
+) {
+    let effects_volume = Volume::Linear(mixer.gain(mixer.effects));
+    // new sounds start with the global volume, until they are mixed in the next frame
+    if mixer.is_changed() {
+        *global_volume = GlobalVolume::new(effects_volume);
+    }
+    for (playback, track, mut sink) in &mut music {
+        sink.set_volume(playback.volume * Volume::Linear(mixer.gain(mixer.music * track.level)));
+    }
+    for (playback, mut sink) in &mut effects {
+        sink.set_volume(playback.volume * effects_volume);
+    }
+    for (playback, mut sink) in &mut spatial_effects {
+        sink.set_volume(playback.volume * effects_volume);
+    }
+}
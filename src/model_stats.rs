@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+
+use bevy::{
+    camera::{primitives::Aabb, RenderTarget},
+    gltf::{Gltf, GltfMaterialName, GltfMeshName},
+    prelude::*,
+    scene::SceneInstanceReady,
+    window::WindowRef,
+};
+use schminput::prelude::*;
+
+use crate::{
+    asset_handler::ASSET_ELEMENTS,
+    debug_overlay::scene_bounds,
+    panel::{panel_text, set_panel_visible, spawn_world_panel, WorldPanel},
+    settings::UserSettings,
+    tools_menu::{Tool, ToolInput},
+    MoveActions,
+};
+
+// the bounds change with the animation and when the model is grabbed
+const BOUNDS_INTERVAL: f32 = 0.5;
+// the headset panel floats this far to the right of the model
+const PANEL_GAP: f32 = 0.3;
+
+#[derive(Resource)]
+pub struct ModelStats {
+    pub visible: bool,
+    // filled when the scene is ready, the bounds line is added on every update
+    lines: Vec<String>,
+    // the catalog files, only the scenes are loaded otherwise and the animation names are in the gltf
+    gltfs: Vec<Handle<Gltf>>,
+    toggle_action: Entity,
+    timer: Timer,
+    panel: Option<WorldPanel>,
+    panel_text: Option<Entity>,
+    // the desktop ui, the normal ui can't be seen in the headset
+    window_node: Option<Entity>,
+    window_text: Option<Entity>,
+}
+
+pub fn setup_model_stats(
+    mut commands: Commands,
+    settings: Res<UserSettings>,
+    asset_server: Res<AssetServer>,
+) {
+    let set = commands.spawn(ActionSet::new("model_stats", "Model Statistics", 1)).id();
+    let toggle_action = commands
+        .spawn((
+            Action::new("toggle_model_stats", "Toggle Model Statistics", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyT).just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    commands.insert_resource(ModelStats {
+        visible: settings.get("model_stats.visible").unwrap_or(false),
+        lines: vec![],
+        gltfs: ASSET_ELEMENTS
+            .iter()
+            .map(|asset| asset_server.load(asset.file_name))
+            .collect(),
+        toggle_action,
+        timer: Timer::from_seconds(BOUNDS_INTERVAL, TimerMode::Repeating),
+        panel: None,
+        panel_text: None,
+        window_node: None,
+        window_text: None,
+    });
+}
+
+// the key on the desktop, in the headset the tools menu
+pub fn toggle_model_stats(
+    mut stats: ResMut<ModelStats>,
+    bool_value: Query<&BoolActionValue>,
+    mut tool_input: MessageReader<ToolInput>,
+    mut settings: ResMut<UserSettings>,
+) {
+    let from_menu = tool_input
+        .read()
+        .filter(|input| input.tool == Tool::ModelStats)
+        .count()
+        % 2
+        == 1;
+    if !from_menu && !bool_value.get(stats.toggle_action).unwrap().any {
+        return;
+    }
+    stats.visible = !stats.visible;
+    info!("model statistics: {}", stats.visible);
+    settings.set("model_stats.visible", stats.visible);
+}
+
+// collects everything that doesn't change while the scene is shown
+pub fn collect_model_stats(
+    trigger: On<SceneInstanceReady>,
+    mut stats: ResMut<ModelStats>,
+    move_actions: Res<MoveActions>,
+    models: Query<(), With<SceneRoot>>,
+    children: Query<&Children>,
+    scene_meshes: Query<(
+        &Mesh3d,
+        Option<&MeshMaterial3d<StandardMaterial>>,
+        Option<&GltfMeshName>,
+        Option<&GltfMaterialName>,
+    )>,
+    meshes: Res<Assets<Mesh>>,
+    materials: Res<Assets<StandardMaterial>>,
+    images: Res<Assets<Image>>,
+    gltfs: Res<Assets<Gltf>>,
+    clips: Res<Assets<AnimationClip>>,
+) {
+    if models.get(trigger.entity).is_err() {
+        return;
+    }
+    let Some(asset) = ASSET_ELEMENTS.get(move_actions.shown_scene) else {
+        return;
+    };
+    let mut mesh_names = vec![];
+    let mut vertices = 0;
+    let mut triangles = 0;
+    let mut morph_targets = vec![];
+    // by asset id, one material or texture is usually shared by several meshes
+    let mut material_names = BTreeMap::new();
+    let mut textures = BTreeMap::new();
+    for entity in children.iter_descendants(trigger.entity) {
+        let Ok((mesh, material, mesh_name, material_name)) = scene_meshes.get(entity) else {
+            continue;
+        };
+        mesh_names.push(mesh_name.map_or("unnamed".to_string(), |name| name.0.clone()));
+        if let Some(mesh) = meshes.get(&mesh.0) {
+            let count = mesh.count_vertices();
+            vertices += count;
+            triangles += mesh.indices().map_or(count, |indices| indices.len()) / 3;
+            for name in mesh.morph_target_names().unwrap_or_default() {
+                if !morph_targets.contains(name) {
+                    morph_targets.push(name.clone());
+                }
+            }
+        }
+        let Some(material) = material else {
+            continue;
+        };
+        material_names.insert(
+            material.0.id(),
+            material_name.map_or("unnamed".to_string(), |name| name.0.clone()),
+        );
+        let Some(material) = materials.get(&material.0) else {
+            continue;
+        };
+        for (slot, texture) in [
+            ("base color", &material.base_color_texture),
+            ("normal", &material.normal_map_texture),
+            ("metallic roughness", &material.metallic_roughness_texture),
+            ("emissive", &material.emissive_texture),
+            ("occlusion", &material.occlusion_texture),
+        ] {
+            let Some(texture) = texture else {
+                continue;
+            };
+            let size = images
+                .get(texture)
+                .map_or("not loaded".to_string(), |image| {
+                    format!("{}x{} {:?}", image.width(), image.height(), image.texture_descriptor.format)
+                });
+            textures.insert(texture.id(), format!("{}, {}", slot, size));
+        }
+    }
+
+    let gltf = stats.gltfs.get(move_actions.shown_scene).and_then(|gltf| gltfs.get(gltf));
+    let animations = gltf.map_or(vec![], |gltf| {
+        gltf.animations
+            .iter()
+            .enumerate()
+            .map(|(index, clip)| {
+                let name = gltf
+                    .named_animations
+                    .iter()
+                    .find(|(_, named)| *named == clip)
+                    .map_or(format!("animation {}", index), |(name, _)| name.to_string());
+                match clips.get(clip) {
+                    Some(clip) => format!("{} ({:.1} s)", name, clip.duration()),
+                    None => name,
+                }
+            })
+            .collect()
+    });
+
+    let mut lines = vec![
+        asset.file_name.to_string(),
+        format!(
+            "{} meshes, {} vertices, {} triangles",
+            mesh_names.len(),
+            vertices,
+            triangles
+        ),
+        format!("meshes: {}", mesh_names.join(", ")),
+        format!(
+            "materials: {}",
+            material_names.into_values().collect::<Vec<_>>().join(", ")
+        ),
+        format!("textures: {}", textures.len()),
+    ];
+    lines.extend(textures.into_values().map(|texture| format!("  {}", texture)));
+    lines.push(format!(
+        "morph targets: {}",
+        if morph_targets.is_empty() { "none".to_string() } else { morph_targets.join(", ") }
+    ));
+    lines.push(format!(
+        "animations: {}",
+        if animations.is_empty() { "none".to_string() } else { animations.join(", ") }
+    ));
+    stats.lines = lines;
+}
+
+pub fn update_model_stats(
+    mut commands: Commands,
+    mut stats: ResMut<ModelStats>,
+    time: Res<Time>,
+    cameras_3d: Query<(Entity, &RenderTarget), With<Camera3d>>,
+    models: Query<Entity, With<SceneRoot>>,
+    children: Query<&Children>,
+    bounds: Query<(&Aabb, &GlobalTransform)>,
+    mut texts: Query<&mut Text>,
+    mut transforms: Query<&mut Transform>,
+    mut cameras: Query<&mut Camera>,
+    targets: Query<&UiTargetCamera>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if stats.is_changed() && stats.panel.is_none() && stats.visible {
+        let panel = spawn_world_panel(
+            &mut commands,
+            &mut images,
+            &mut meshes,
+            &mut materials,
+            UVec2::new(640, 640),
+            0.4,
+        );
+        let text = commands.spawn(panel_text("", 16.0)).id();
+        commands.entity(panel.root).add_child(text);
+        stats.panel_text = Some(text);
+        stats.panel = Some(panel);
+        // the desktop camera from setup, the xr cameras render into the swapchain
+        if let Some((camera, _)) = cameras_3d
+            .iter()
+            .find(|(_, target)| matches!(target, RenderTarget::Window(WindowRef::Primary)))
+        {
+            let text = commands.spawn(panel_text("", 16.0)).id();
+            let node = commands
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        top: px(12),
+                        left: px(12),
+                        max_width: px(560),
+                        padding: UiRect::all(px(8)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.05, 0.05, 0.08, 0.85)),
+                    UiTargetCamera(camera),
+                ))
+                .add_child(text)
+                .id();
+            stats.window_text = Some(text);
+            stats.window_node = Some(node);
+        }
+    } else if stats.is_changed()
+        && let Some(panel) = &stats.panel
+    {
+        set_panel_visible(&mut commands, panel, &mut cameras, &targets, stats.visible);
+        if let Some(node) = stats.window_node {
+            commands.entity(node).insert(if stats.visible {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            });
+        }
+    }
+
+    let stats = stats.bypass_change_detection();
+    if !stats.visible || !stats.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let scene_bounds = scene_bounds(&models, &children, &bounds);
+    let mut lines = stats.lines.clone();
+    if lines.is_empty() {
+        lines.push("no scene loaded".to_string());
+    }
+    if let Some(scene_bounds) = scene_bounds {
+        let size = scene_bounds.max - scene_bounds.min;
+        lines.push(format!("bounds {:.2} x {:.2} x {:.2} m", size.x, size.y, size.z));
+    }
+    let content = lines.join("\n");
+    for text in [stats.panel_text, stats.window_text].into_iter().flatten() {
+        if let Ok(mut text) = texts.get_mut(text) {
+            text.0.clone_from(&content);
+        }
+    }
+    // world locked to the right of the model, the model is usually in front of the player
+    if let (Some(panel), Some(scene_bounds)) = (&stats.panel, scene_bounds)
+        && let Ok(mut transform) = transforms.get_mut(panel.quad)
+    {
+        let center = Vec3::from(scene_bounds.min + scene_bounds.max) * 0.5;
+        let right = scene_bounds.max.x + PANEL_GAP;
+        transform.translation = Vec3::new(right, center.y, center.z);
+    }
+}
@@ -0,0 +1,101 @@
+use bevy::{
+    camera::RenderTarget,
+    prelude::*,
+    render::render_resource::TextureFormat,
+};
+
+// a bevy ui rendered into a texture which is shown on a quad in the scene,
+// the normal ui can't be seen in the headset
+pub struct WorldPanel {
+    // the quad, position it or attach it to something
+    pub quad: Entity,
+    // the ui root node, spawn the panel content as its children
+    pub root: Entity,
+}
+
+pub fn spawn_world_panel(
+    commands: &mut Commands,
+    images: &mut Assets<Image>,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    size_px: UVec2,
+    width_m: f32,
+) -> WorldPanel {
+    let image = images.add(Image::new_target_texture(
+        size_px.x,
+        size_px.y,
+        TextureFormat::bevy_default(),
+        None,
+    ));
+    let camera = commands
+        .spawn((
+            Camera2d,
+            Camera {
+                // render the panel before the views that show it
+                order: -1,
+                clear_color: ClearColorConfig::Custom(Color::srgba(0.05, 0.05, 0.08, 0.85)),
+                ..default()
+            },
+            RenderTarget::Image(image.clone().into()),
+        ))
+        .id();
+    let root = commands
+        .spawn((
+            Node {
+                width: percent(100),
+                height: percent(100),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(px(12)),
+                row_gap: px(4),
+                ..default()
+            },
+            UiTargetCamera(camera),
+        ))
+        .id();
+    let height_m = width_m * size_px.y as f32 / size_px.x as f32;
+    let quad = commands
+        .spawn((
+            Mesh3d(meshes.add(Rectangle::new(width_m, height_m))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color_texture: Some(image),
+                unlit: true,
+                alpha_mode: AlphaMode::Blend,
+                ..default()
+            })),
+            Transform::default(),
+            Visibility::default(),
+        ))
+        .id();
+    WorldPanel { quad, root }
+}
+
+// shows or hides the quad and stops rendering the texture while hidden
+pub fn set_panel_visible(
+    commands: &mut Commands,
+    panel: &WorldPanel,
+    cameras: &mut Query<&mut Camera>,
+    targets: &Query<&UiTargetCamera>,
+    visible: bool,
+) {
+    commands.entity(panel.quad).insert(if visible {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    });
+    if let Ok(target) = targets.get(panel.root)
+        && let Ok(mut camera) = cameras.get_mut(target.0)
+    {
+        camera.is_active = visible;
+    }
+}
+
+pub fn panel_text(text: impl Into<String>, font_size: f32) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+    )
+}
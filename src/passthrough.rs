@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+use bevy_mod_openxr::resources::{OxrPassthrough, OxrPassthroughLayerFB};
+use schminput::prelude::*;
+
+use crate::{capabilities::XrCapabilities, tools_menu::LONG_PRESS};
+
+#[derive(Resource)]
+pub struct PassthroughMode {
+    // false means full vr, see environment.rs
+    pub enabled: bool,
+    available: bool,
+    toggle_action: Entity,
+    // seconds the button is held, holding it opens the tools menu instead
+    held: f32,
+}
+
+pub fn setup_passthrough(mut commands: Commands, capabilities: Res<XrCapabilities>) {
+    let set = commands.spawn(ActionSet::new("passthrough", "Passthrough", 1)).id();
+    let toggle_action = commands
+        .spawn((
+            Action::new("toggle_passthrough", "Toggle Passthrough", set),
+            capabilities.bindings("/user/hand/left/input/menu/click"),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyP)),
+            BoolActionValue::new(),
+        ))
+        .id();
+    commands.insert_resource(PassthroughMode {
+        enabled: capabilities.passthrough,
+        available: capabilities.passthrough,
+        toggle_action,
+        held: 0.0,
+    });
+}
+
+// toggles when the button is released after a short press
+pub fn toggle_passthrough(
+    mut mode: ResMut<PassthroughMode>,
+    bool_value: Query<&BoolActionValue>,
+    time: Res<Time>,
+) {
+    if bool_value.get(mode.toggle_action).unwrap().any {
+        mode.bypass_change_detection().held += time.delta_secs();
+        return;
+    }
+    let held = std::mem::take(&mut mode.bypass_change_detection().held);
+    if held == 0.0 || held >= LONG_PRESS {
+        return;
+    }
+    if !mode.available {
+        warn!("passthrough not supported by the runtime, staying in vr mode");
+        return;
+    }
+    mode.enabled = !mode.enabled;
+    info!("passthrough: {}", mode.enabled);
+}
+
+// starts or pauses the passthrough layer, the clear color is set by the environment
+pub fn apply_passthrough(
+    mode: Res<PassthroughMode>,
+    passthrough: Option<Res<OxrPassthrough>>,
+    passthrough_layer: Option<Res<OxrPassthroughLayerFB>>,
+) {
+    let (Some(passthrough), Some(layer)) = (passthrough, passthrough_layer) else {
+        return;
+    };
+    // the passthrough resources are created with the session, after the mode was set up
+    if !mode.is_changed() && !passthrough.is_added() {
+        return;
+    }
+    let result = if mode.enabled {
+        passthrough.start().and_then(|_| layer.resume())
+    } else {
+        layer.pause().and_then(|_| passthrough.pause())
+    };
+    if let Err(err) = result {
+        warn!("unable to switch passthrough: {}", err);
+    }
+}
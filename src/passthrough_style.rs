@@ -0,0 +1,388 @@
+use std::{ffi::c_void, ptr};
+
+use bevy::prelude::*;
+use bevy_mod_openxr::resources::{OxrInstance, OxrPassthroughLayerFB};
+use schminput::prelude::*;
+
+use crate::{
+    capabilities::XrCapabilities,
+    panel::{panel_text, set_panel_visible, spawn_world_panel, WorldPanel},
+    settings::UserSettings,
+    HandLeft, MoveActions,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PassthroughColorMap {
+    None,
+    Grayscale,
+    // grayscale mapped onto the tint color
+    Monochrome,
+    BrightnessContrastSaturation,
+}
+
+impl PassthroughColorMap {
+    const ALL: [Self; 4] = [
+        Self::None,
+        Self::Grayscale,
+        Self::Monochrome,
+        Self::BrightnessContrastSaturation,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Grayscale => "grayscale",
+            Self::Monochrome => "monochrome",
+            Self::BrightnessContrastSaturation => "bcs",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|map| map.name() == name)
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct PassthroughStyle {
+    // 0.0 - 1.0
+    pub opacity: f32,
+    // alpha 0.0 disables the edge rendering
+    pub edge_color: Color,
+    pub color_map: PassthroughColorMap,
+    pub tint: Color,
+    // -100.0 - 100.0
+    pub brightness: f32,
+    // 1.0 keeps the camera image
+    pub contrast: f32,
+    pub saturation: f32,
+}
+
+impl Default for PassthroughStyle {
+    fn default() -> Self {
+        Self {
+            opacity: 1.0,
+            edge_color: Color::NONE,
+            color_map: PassthroughColorMap::None,
+            tint: Color::WHITE,
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+        }
+    }
+}
+
+impl PassthroughStyle {
+    pub fn load(settings: &UserSettings) -> Self {
+        let default = Self::default();
+        let hsva = |key: &str, default: Color| {
+            settings
+                .get::<f32>(&format!("{}.hue", key))
+                .map(|hue| {
+                    let alpha = settings.get::<f32>(&format!("{}.alpha", key)).unwrap_or(1.0);
+                    Color::hsva(hue, 1.0, 1.0, alpha)
+                })
+                .unwrap_or(default)
+        };
+        Self {
+            opacity: settings.get("passthrough.opacity").unwrap_or(default.opacity),
+            edge_color: hsva("passthrough.edge", default.edge_color),
+            color_map: settings
+                .get::<String>("passthrough.color_map")
+                .and_then(|name| PassthroughColorMap::from_name(&name))
+                .unwrap_or(default.color_map),
+            tint: hsva("passthrough.tint", default.tint),
+            brightness: settings.get("passthrough.brightness").unwrap_or(default.brightness),
+            contrast: settings.get("passthrough.contrast").unwrap_or(default.contrast),
+            saturation: settings.get("passthrough.saturation").unwrap_or(default.saturation),
+        }
+    }
+
+    pub fn store(&self, settings: &mut UserSettings) {
+        let edge = Hsva::from(self.edge_color);
+        let tint = Hsva::from(self.tint);
+        settings.set("passthrough.opacity", self.opacity);
+        settings.set("passthrough.edge.hue", edge.hue);
+        settings.set("passthrough.edge.alpha", edge.alpha);
+        settings.set("passthrough.color_map", self.color_map.name());
+        settings.set("passthrough.tint.hue", tint.hue);
+        settings.set("passthrough.tint.alpha", tint.alpha);
+        settings.set("passthrough.brightness", self.brightness);
+        settings.set("passthrough.contrast", self.contrast);
+        settings.set("passthrough.saturation", self.saturation);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StyleField {
+    Opacity,
+    EdgeStrength,
+    EdgeHue,
+    ColorMap,
+    TintHue,
+    Brightness,
+    Contrast,
+    Saturation,
+}
+
+const STYLE_FIELDS: [StyleField; 8] = [
+    StyleField::Opacity,
+    StyleField::EdgeStrength,
+    StyleField::EdgeHue,
+    StyleField::ColorMap,
+    StyleField::TintHue,
+    StyleField::Brightness,
+    StyleField::Contrast,
+    StyleField::Saturation,
+];
+
+impl StyleField {
+    fn label(&self, style: &PassthroughStyle) -> String {
+        let edge = Hsva::from(style.edge_color);
+        match self {
+            Self::Opacity => format!("opacity: {:.2}", style.opacity),
+            Self::EdgeStrength => format!("edge strength: {:.2}", edge.alpha),
+            Self::EdgeHue => format!("edge hue: {:.0}", edge.hue),
+            Self::ColorMap => format!("color map: {}", style.color_map.name()),
+            Self::TintHue => format!("tint hue: {:.0}", Hsva::from(style.tint).hue),
+            Self::Brightness => format!("brightness: {:.0}", style.brightness),
+            Self::Contrast => format!("contrast: {:.2}", style.contrast),
+            Self::Saturation => format!("saturation: {:.2}", style.saturation),
+        }
+    }
+
+    // value is the thumbstick x times the frame time
+    fn adjust(&self, style: &mut PassthroughStyle, value: f32) {
+        let shift_hue = |color: Color, value: f32| {
+            let hsva = Hsva::from(color);
+            Color::hsva((hsva.hue + value * 90.0).rem_euclid(360.0), 1.0, 1.0, hsva.alpha)
+        };
+        match self {
+            Self::Opacity => style.opacity = (style.opacity + value * 0.5).clamp(0.0, 1.0),
+            Self::EdgeStrength => {
+                let alpha = style.edge_color.alpha();
+                style.edge_color.set_alpha((alpha + value * 0.5).clamp(0.0, 1.0));
+            }
+            Self::EdgeHue => style.edge_color = shift_hue(style.edge_color, value),
+            Self::ColorMap => {}
+            Self::TintHue => style.tint = shift_hue(style.tint, value),
+            Self::Brightness => {
+                style.brightness = (style.brightness + value * 50.0).clamp(-100.0, 100.0)
+            }
+            Self::Contrast => style.contrast = (style.contrast + value * 0.5).clamp(0.0, 4.0),
+            Self::Saturation => {
+                style.saturation = (style.saturation + value * 0.5).clamp(0.0, 4.0)
+            }
+        }
+    }
+
+    // for fields without a continuous value
+    fn step(&self, style: &mut PassthroughStyle, direction: isize) {
+        if *self == Self::ColorMap {
+            let maps = PassthroughColorMap::ALL;
+            let index = maps.iter().position(|map| *map == style.color_map).unwrap_or(0);
+            let index = (index as isize + direction).rem_euclid(maps.len() as isize);
+            style.color_map = maps[index as usize];
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct PassthroughStyleMenu {
+    pub open: bool,
+    selected: usize,
+    toggle_action: Entity,
+    toggle_released: bool,
+    // like TurnState, one step per thumbstick movement
+    navigate_ready: bool,
+    panel: Option<WorldPanel>,
+    lines: Vec<Entity>,
+}
+
+pub fn setup_passthrough_style(
+    mut commands: Commands,
+    capabilities: Res<XrCapabilities>,
+    settings: Res<UserSettings>,
+) {
+    let set = commands.spawn(ActionSet::new("passthrough_style", "Passthrough Style", 1)).id();
+    let toggle_action = commands
+        .spawn((
+            Action::new("toggle_passthrough_style", "Toggle Passthrough Style Menu", set),
+            capabilities.bindings("/user/hand/right/input/b/click"),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyO)),
+            BoolActionValue::new(),
+        ))
+        .id();
+    commands.insert_resource(PassthroughStyle::load(&settings));
+    commands.insert_resource(PassthroughStyleMenu {
+        open: false,
+        selected: 0,
+        toggle_action,
+        toggle_released: true,
+        navigate_ready: true,
+        panel: None,
+        lines: vec![],
+    });
+}
+
+pub fn toggle_passthrough_style_menu(
+    mut commands: Commands,
+    mut menu: ResMut<PassthroughStyleMenu>,
+    bool_value: Query<&BoolActionValue>,
+    style: Res<PassthroughStyle>,
+    mut settings: ResMut<UserSettings>,
+    left_hand: Query<Entity, With<HandLeft>>,
+    mut cameras: Query<&mut Camera>,
+    targets: Query<&UiTargetCamera>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !bool_value.get(menu.toggle_action).unwrap().any {
+        menu.toggle_released = true;
+        return;
+    }
+    if !menu.toggle_released {
+        return;
+    }
+    menu.toggle_released = false;
+    menu.open = !menu.open;
+    if !menu.open {
+        // only persist when done, not on every small thumbstick step
+        style.store(&mut settings);
+    }
+    if menu.panel.is_none() {
+        let panel = spawn_world_panel(
+            &mut commands,
+            &mut images,
+            &mut meshes,
+            &mut materials,
+            UVec2::new(512, 320),
+            0.2,
+        );
+        // above the left controller, tilted towards the head
+        commands.entity(panel.quad).insert(
+            Transform::from_xyz(0.0, 0.12, -0.05).with_rotation(Quat::from_rotation_x(-0.5)),
+        );
+        if let Ok(hand) = left_hand.single() {
+            commands.entity(hand).add_child(panel.quad);
+        }
+        commands.entity(panel.root).with_child(panel_text("Passthrough style", 28.0));
+        let lines = STYLE_FIELDS
+            .iter()
+            .map(|field| {
+                let line = commands.spawn(panel_text(field.label(&style), 22.0)).id();
+                commands.entity(panel.root).add_child(line);
+                line
+            })
+            .collect();
+        menu.lines = lines;
+        menu.panel = Some(panel);
+    } else if let Some(panel) = &menu.panel {
+        set_panel_visible(&mut commands, panel, &mut cameras, &targets, menu.open);
+    }
+}
+
+// left thumbstick: up/down selects a field, left/right changes it
+pub fn edit_passthrough_style(
+    mut menu: ResMut<PassthroughStyleMenu>,
+    move_actions: Res<MoveActions>,
+    vec2_value: Query<&Vec2ActionValue>,
+    time: Res<Time>,
+    mut style: ResMut<PassthroughStyle>,
+    mut texts: Query<(&mut Text, &mut TextColor)>,
+) {
+    if !menu.open {
+        return;
+    }
+    let stick = vec2_value.get(move_actions.move_action).unwrap().any;
+    if menu.navigate_ready && stick.y.abs() > 0.8 {
+        let count = STYLE_FIELDS.len();
+        menu.selected = if stick.y > 0.0 {
+            (menu.selected + count - 1) % count
+        } else {
+            (menu.selected + 1) % count
+        };
+        menu.navigate_ready = false;
+    } else if menu.navigate_ready && stick.x.abs() > 0.8 {
+        STYLE_FIELDS[menu.selected].step(&mut style, stick.x.signum() as isize);
+        menu.navigate_ready = false;
+    }
+    if stick.length() < 0.2 {
+        menu.navigate_ready = true;
+    }
+    if stick.x.abs() > 0.2 && stick.y.abs() < 0.5 {
+        STYLE_FIELDS[menu.selected].adjust(&mut style, stick.x * time.delta_secs());
+    }
+    for (index, (field, line)) in STYLE_FIELDS.iter().zip(&menu.lines).enumerate() {
+        if let Ok((mut text, mut color)) = texts.get_mut(*line) {
+            **text = field.label(&style);
+            color.0 = if index == menu.selected {
+                Color::srgb(1.0, 0.8, 0.2)
+            } else {
+                Color::WHITE
+            };
+        }
+    }
+}
+
+pub fn apply_passthrough_style(
+    style: Res<PassthroughStyle>,
+    instance: Option<Res<OxrInstance>>,
+    layer: Option<Res<OxrPassthroughLayerFB>>,
+) {
+    let (Some(instance), Some(layer)) = (instance, layer) else {
+        return;
+    };
+    if !style.is_changed() && !layer.is_added() {
+        return;
+    }
+    let Some(fp) = instance.exts().fb_passthrough.as_ref() else {
+        return;
+    };
+    let color4f = |color: Color| {
+        let color = color.to_linear();
+        openxr::sys::Color4f {
+            r: color.red,
+            g: color.green,
+            b: color.blue,
+            a: color.alpha,
+        }
+    };
+    let mono_to_mono = openxr::sys::PassthroughColorMapMonoToMonoFB {
+        ty: openxr::sys::PassthroughColorMapMonoToMonoFB::TYPE,
+        next: ptr::null(),
+        texture_color_map: std::array::from_fn(|i| i as u8),
+    };
+    let mono_to_rgba = openxr::sys::PassthroughColorMapMonoToRgbaFB {
+        ty: openxr::sys::PassthroughColorMapMonoToRgbaFB::TYPE,
+        next: ptr::null(),
+        texture_color_map: std::array::from_fn(|i| {
+            let tint = style.tint.to_linear() * (i as f32 / 255.0);
+            color4f(tint.with_alpha(1.0).into())
+        }),
+    };
+    let bcs = openxr::sys::PassthroughBrightnessContrastSaturationFB {
+        ty: openxr::sys::PassthroughBrightnessContrastSaturationFB::TYPE,
+        next: ptr::null(),
+        brightness: style.brightness,
+        contrast: style.contrast,
+        saturation: style.saturation,
+    };
+    let next: *const c_void = match style.color_map {
+        PassthroughColorMap::None => ptr::null(),
+        PassthroughColorMap::Grayscale => &mono_to_mono as *const _ as *const c_void,
+        PassthroughColorMap::Monochrome => &mono_to_rgba as *const _ as *const c_void,
+        PassthroughColorMap::BrightnessContrastSaturation => &bcs as *const _ as *const c_void,
+    };
+    let xr_style = openxr::sys::PassthroughStyleFB {
+        ty: openxr::sys::PassthroughStyleFB::TYPE,
+        next,
+        texture_opacity_factor: style.opacity,
+        edge_color: color4f(style.edge_color),
+    };
+    // the color map structs have to outlive this call
+    let result = unsafe { (fp.passthrough_layer_set_style)(layer.inner(), &xr_style) };
+    if result != openxr::sys::Result::SUCCESS {
+        warn!("unable to set the passthrough style: {}", result);
+    }
+}
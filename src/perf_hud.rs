@@ -0,0 +1,272 @@
+use bevy::{
+    diagnostic::{
+        DiagnosticsStore, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin,
+        SystemInformationDiagnosticsPlugin,
+    },
+    mesh::{Indices, VertexAttributeValues},
+    prelude::*,
+};
+use bevy_mod_xr::session::XrTrackingRoot;
+use schminput::prelude::*;
+
+use crate::{
+    panel::{panel_text, set_panel_visible, spawn_world_panel, WorldPanel},
+    settings::UserSettings,
+    tools_menu::{Tool, ToolInput},
+    HeadsetView,
+};
+
+// counting the scene meshes walks every vertex buffer, so only twice a second
+const HUD_INTERVAL: f32 = 0.5;
+// a frame that took this much longer than the budget counts as dropped
+const DROPPED_FACTOR: f32 = 1.5;
+// below the line of sight, so it doesn't cover the model
+const HEAD_OFFSET: Vec3 = Vec3::new(0.0, -0.2, -0.6);
+
+#[derive(Resource)]
+pub struct PerfHud {
+    pub visible: bool,
+    // follows the head, otherwise it stays where it was opened
+    pub head_locked: bool,
+    // the display refresh rate, 72 hz is the quest default
+    target_fps: f32,
+    dropped_frames: u32,
+    frames: u32,
+    timer: Timer,
+    toggle_action: Entity,
+    panel: Option<WorldPanel>,
+    lines: Vec<Entity>,
+}
+
+#[derive(Default)]
+struct SceneStats {
+    entities: usize,
+    meshes: usize,
+    triangles: usize,
+    vertices: usize,
+    // every visible mesh is at least one draw, the real count depends on batching
+    draws: usize,
+}
+
+pub fn setup_perf_hud(mut commands: Commands, settings: Res<UserSettings>) {
+    let set = commands.spawn(ActionSet::new("perf_hud", "Performance HUD", 1)).id();
+    let toggle_action = commands
+        .spawn((
+            Action::new("toggle_perf_hud", "Toggle Performance HUD", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::F3).just_pressed()),
+            GamepadBindings::new()
+                .bind(GamepadBinding::new(GamepadBindingSource::Start).button_just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    let default_fps = if cfg!(target_os = "android") { 72.0 } else { 90.0 };
+    commands.insert_resource(PerfHud {
+        visible: settings.get("perf.hud").unwrap_or(false),
+        head_locked: settings.get("perf.head_locked").unwrap_or(true),
+        target_fps: settings.get("perf.target_fps").unwrap_or(default_fps),
+        dropped_frames: 0,
+        frames: 0,
+        timer: Timer::from_seconds(HUD_INTERVAL, TimerMode::Repeating),
+        toggle_action,
+        panel: None,
+        lines: vec![],
+    });
+}
+
+// the key or gamepad, in the headset the tools menu
+pub fn toggle_perf_hud(
+    mut hud: ResMut<PerfHud>,
+    bool_value: Query<&BoolActionValue>,
+    mut tool_input: MessageReader<ToolInput>,
+    mut settings: ResMut<UserSettings>,
+) {
+    let from_menu = tool_input
+        .read()
+        .filter(|input| input.tool == Tool::PerfHud)
+        .count()
+        % 2
+        == 1;
+    if !from_menu && !bool_value.get(hud.toggle_action).unwrap().any {
+        return;
+    }
+    hud.visible = !hud.visible;
+    hud.dropped_frames = 0;
+    hud.frames = 0;
+    info!("performance hud: {}", hud.visible);
+    settings.set("perf.hud", hud.visible);
+}
+
+// in front of the head, a world locked hud is only moved when it is opened
+pub fn place_perf_hud(
+    mut commands: Commands,
+    mut hud: ResMut<PerfHud>,
+    root: Query<&Transform, (With<XrTrackingRoot>, Without<HeadsetView>)>,
+    headset: Query<&Transform, (With<HeadsetView>, Without<XrTrackingRoot>)>,
+    mut transforms: Query<&mut Transform, (Without<XrTrackingRoot>, Without<HeadsetView>)>,
+    mut cameras: Query<&mut Camera>,
+    targets: Query<&UiTargetCamera>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if hud.is_changed() {
+        if let Some(panel) = &hud.panel {
+            set_panel_visible(&mut commands, panel, &mut cameras, &targets, hud.visible);
+        } else if hud.visible {
+            let panel = spawn_world_panel(
+                &mut commands,
+                &mut images,
+                &mut meshes,
+                &mut materials,
+                UVec2::new(512, 320),
+                0.3,
+            );
+            let lines = (0..8)
+                .map(|_| {
+                    let line = commands.spawn(panel_text("", 22.0)).id();
+                    commands.entity(panel.root).add_child(line);
+                    line
+                })
+                .collect();
+            hud.lines = lines;
+            hud.panel = Some(panel);
+            // the quad is spawned with the commands, place it next frame
+            return;
+        }
+    }
+    let Some(panel) = &hud.panel else {
+        return;
+    };
+    if !hud.visible || (!hud.head_locked && !hud.is_changed()) {
+        return;
+    }
+    // the headset pose is relative to the tracking root, on the desktop there is neither
+    let head = match (root.single(), headset.single()) {
+        (Ok(root), Ok(headset)) => root.mul_transform(*headset),
+        _ => Transform::from_xyz(0.0, 1.5, 1.0),
+    };
+    if let Ok(mut transform) = transforms.get_mut(panel.quad) {
+        // tilted up towards the eyes
+        *transform = Transform::from_translation(head.transform_point(HEAD_OFFSET))
+            .with_rotation(head.rotation * Quat::from_rotation_x(0.3));
+    }
+}
+
+fn scene_stats(
+    models: &Query<Entity, With<SceneRoot>>,
+    children: &Query<&Children>,
+    scene_meshes: &Query<(&Mesh3d, &ViewVisibility)>,
+    meshes: &Assets<Mesh>,
+) -> SceneStats {
+    let mut stats = SceneStats::default();
+    for entity in models.iter().flat_map(|model| children.iter_descendants(model)) {
+        stats.entities += 1;
+        let Ok((mesh, visibility)) = scene_meshes.get(entity) else {
+            continue;
+        };
+        stats.meshes += 1;
+        if visibility.get() {
+            stats.draws += 1;
+        }
+        let Some(mesh) = meshes.get(&mesh.0) else {
+            continue;
+        };
+        let vertices = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float32x3(positions)) => positions.len(),
+            _ => 0,
+        };
+        stats.vertices += vertices;
+        stats.triangles += match mesh.indices() {
+            Some(Indices::U16(indices)) => indices.len() / 3,
+            Some(Indices::U32(indices)) => indices.len() / 3,
+            None => vertices / 3,
+        };
+    }
+    stats
+}
+
+// the gpu time is the sum of the top level render passes, nested passes are part of those,
+// timestamps are only supported on vulkan and dx12
+fn gpu_time(diagnostics: &DiagnosticsStore) -> Option<f64> {
+    diagnostics
+        .iter()
+        .filter(|diagnostic| {
+            let path = diagnostic.path().as_str();
+            path.starts_with("render/")
+                && path.ends_with("/elapsed_gpu")
+                && path.split('/').count() == 3
+        })
+        .filter_map(|diagnostic| diagnostic.smoothed())
+        .reduce(|a, b| a + b)
+}
+
+pub fn update_perf_hud(
+    mut hud: ResMut<PerfHud>,
+    time: Res<Time>,
+    diagnostics: Res<DiagnosticsStore>,
+    models: Query<Entity, With<SceneRoot>>,
+    children: Query<&Children>,
+    scene_meshes: Query<(&Mesh3d, &ViewVisibility)>,
+    meshes: Res<Assets<Mesh>>,
+    mut texts: Query<(&mut Text, &mut TextColor)>,
+) {
+    if !hud.visible {
+        return;
+    }
+    // counted every frame, the diagnostics only keep a smoothed value
+    let budget = 1.0 / hud.target_fps;
+    let hud = hud.bypass_change_detection();
+    hud.frames += 1;
+    if time.delta_secs() > budget * DROPPED_FACTOR {
+        hud.dropped_frames += 1;
+    }
+    if !hud.timer.tick(time.delta()).just_finished() || hud.lines.is_empty() {
+        return;
+    }
+    let value = |path| diagnostics.get(path).and_then(|diagnostic| diagnostic.smoothed());
+    let frame_time = value(&FrameTimeDiagnosticsPlugin::FRAME_TIME).unwrap_or_default();
+    let fps = value(&FrameTimeDiagnosticsPlugin::FPS).unwrap_or_default();
+    let budget_ms = budget as f64 * 1000.0;
+    let stats = scene_stats(&models, &children, &scene_meshes, &meshes);
+    let over_budget = frame_time > budget_ms;
+
+    let lines = [
+        format!("{:.1} ms / {:.1} ms budget, {:.0} fps", frame_time, budget_ms, fps),
+        format!("dropped {} of {} frames", hud.dropped_frames, hud.frames),
+        match gpu_time(&diagnostics) {
+            Some(gpu) => format!("gpu {:.1} ms", gpu),
+            None => "gpu n/a".to_string(),
+        },
+        match value(&SystemInformationDiagnosticsPlugin::PROCESS_CPU_USAGE) {
+            Some(cpu) => format!("cpu {:.0}%", cpu),
+            None => "cpu n/a".to_string(),
+        },
+        format!(
+            "entities {:.0} total, {} in scene",
+            value(&EntityCountDiagnosticsPlugin::ENTITY_COUNT).unwrap_or_default(),
+            stats.entities
+        ),
+        format!("meshes {}, draws ~{}", stats.meshes, stats.draws),
+        format!("triangles {}, vertices {}", stats.triangles, stats.vertices),
+        match (
+            value(&SystemInformationDiagnosticsPlugin::PROCESS_MEM_USAGE),
+            value(&SystemInformationDiagnosticsPlugin::SYSTEM_MEM_USAGE),
+        ) {
+            (Some(process), Some(system)) => {
+                format!("memory {:.2} GiB, system {:.0}%", process, system)
+            }
+            _ => "memory n/a".to_string(),
+        },
+    ];
+    for (index, (line, content)) in hud.lines.iter().zip(lines).enumerate() {
+        if let Ok((mut text, mut color)) = texts.get_mut(*line) {
+            text.0 = content;
+            // the frame time line turns red over budget
+            color.0 = if index == 0 && over_budget {
+                Color::srgb(1.0, 0.3, 0.3)
+            } else {
+                Color::WHITE
+            };
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use bevy::{color::palettes::css, prelude::*};
+
+use crate::haptics::Hand;
+
+// the aim pose entities, the ray starts at their origin and points along -Z
+#[derive(Component, Clone, Copy)]
+pub struct AimLeft;
+#[derive(Component, Clone, Copy)]
+pub struct AimRight;
+
+// sent every frame a laser hits a mesh of the shown model
+#[derive(Message, Clone, Copy, Debug)]
+pub struct PointerHit {
+    pub hand: Hand,
+    pub entity: Entity,
+    pub point: Vec3,
+    pub normal: Vec3,
+}
+
+#[derive(Resource)]
+pub struct LaserPointers {
+    pub max_length: f32,
+    pub show: bool,
+    // the latest hit per hand, for systems that only care about the current state
+    pub left_hit: Option<PointerHit>,
+    pub right_hit: Option<PointerHit>,
+}
+
+impl Default for LaserPointers {
+    fn default() -> Self {
+        Self {
+            max_length: 5.0,
+            show: true,
+            left_hit: None,
+            right_hit: None,
+        }
+    }
+}
+
+impl LaserPointers {
+    pub fn hit(&self, hand: Hand) -> Option<&PointerHit> {
+        match hand {
+            Hand::Left => self.left_hit.as_ref(),
+            Hand::Right => self.right_hit.as_ref(),
+        }
+    }
+}
+
+pub fn cast_laser_pointers(
+    mut ray_cast: MeshRayCast,
+    mut lasers: ResMut<LaserPointers>,
+    left_aim: Query<&GlobalTransform, With<AimLeft>>,
+    right_aim: Query<&GlobalTransform, With<AimRight>>,
+    parents: Query<&ChildOf>,
+    models: Query<(), With<SceneRoot>>,
+    mut hits: MessageWriter<PointerHit>,
+    mut gizmos: Gizmos,
+) {
+    // only the model and what is attached to it, e.g. voice notes,
+    // not the controllers, the ground, the shadow floor or the panels
+    let filter = |entity: Entity| {
+        parents
+            .iter_ancestors(entity)
+            .any(|ancestor| models.contains(ancestor))
+    };
+    let settings = MeshRayCastSettings::default().with_filter(&filter);
+    for (hand, aim, color) in [
+        (Hand::Left, left_aim.single(), css::BLUE),
+        (Hand::Right, right_aim.single(), css::RED),
+    ] {
+        let hit = aim.ok().and_then(|aim| {
+            let ray = Ray3d::new(aim.translation(), aim.forward());
+            let hit = ray_cast
+                .cast_ray(ray, &settings)
+                .first()
+                .filter(|(_, hit)| hit.distance <= lasers.max_length)
+                .map(|(entity, hit)| PointerHit {
+                    hand,
+                    entity: *entity,
+                    point: hit.point,
+                    normal: hit.normal.normalize_or_zero(),
+                });
+            if lasers.show {
+                let end = hit
+                    .map(|hit| hit.point)
+                    .unwrap_or(ray.get_point(lasers.max_length));
+                gizmos.line(ray.origin, end, color);
+                if let Some(hit) = hit {
+                    gizmos.sphere(Isometry3d::from_translation(hit.point), 0.01, color);
+                }
+            }
+            hit
+        });
+        if let Some(hit) = hit {
+            hits.write(hit);
+        }
+        match hand {
+            Hand::Left => lasers.left_hit = hit,
+            Hand::Right => lasers.right_hit = hit,
+        }
+    }
+}
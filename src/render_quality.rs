@@ -0,0 +1,197 @@
+use bevy::{
+    core_pipeline::tonemapping::Tonemapping,
+    prelude::*,
+    render::view::{Hdr, NoIndirectDrawing},
+};
+use bevy_mod_openxr::resources::OxrGraphicsInfo;
+use schminput::prelude::*;
+
+use crate::{
+    settings::UserSettings,
+    tools_menu::{Tool, ToolInput},
+};
+
+pub struct RenderQuality {
+    pub name: &'static str,
+    // of the recommended per eye size, the swapchain is created with the session,
+    // so a new scale only takes effect on the next start
+    pub render_scale: f32,
+    pub msaa: Msaa,
+    // indirect drawing is broken on some mobile gpus, e.g. the quest
+    pub indirect_drawing: bool,
+    pub hdr: bool,
+    pub tonemapping: Tonemapping,
+}
+
+// each platform only cycles through its own profiles
+#[cfg(target_os = "android")]
+pub static QUALITY_PROFILES: &[RenderQuality] = &[
+    // the old fixed settings
+    RenderQuality {
+        name: "quest",
+        render_scale: 1.0,
+        msaa: Msaa::Off,
+        indirect_drawing: false,
+        hdr: false,
+        tonemapping: Tonemapping::None,
+    },
+    RenderQuality {
+        name: "quest_msaa",
+        render_scale: 0.8,
+        msaa: Msaa::Sample4,
+        indirect_drawing: false,
+        hdr: false,
+        tonemapping: Tonemapping::None,
+    },
+];
+
+#[cfg(not(target_os = "android"))]
+pub static QUALITY_PROFILES: &[RenderQuality] = &[
+    RenderQuality {
+        name: "desktop",
+        render_scale: 1.0,
+        msaa: Msaa::Sample4,
+        indirect_drawing: true,
+        hdr: true,
+        tonemapping: Tonemapping::AcesFitted,
+    },
+    RenderQuality {
+        name: "desktop_high",
+        render_scale: 1.4,
+        msaa: Msaa::Sample8,
+        indirect_drawing: true,
+        hdr: true,
+        tonemapping: Tonemapping::AcesFitted,
+    },
+];
+
+// a saved name of the other platform falls back to the first profile
+fn saved_profile(settings: &UserSettings) -> usize {
+    settings
+        .get::<String>("render_quality")
+        .and_then(|name| QUALITY_PROFILES.iter().position(|profile| profile.name == name))
+        .unwrap_or(0)
+}
+
+// the per eye size the runtime recommended in the last session, unknown on the first start
+fn recommended_resolution(settings: &UserSettings) -> Option<UVec2> {
+    match (
+        settings.get("xr.recommended_width"),
+        settings.get("xr.recommended_height"),
+    ) {
+        (Some(width), Some(height)) => Some(UVec2::new(width, height)),
+        _ => None,
+    }
+}
+
+// swapchain size for the OpenXR plugin, None leaves it to the runtime
+pub fn swapchain_resolutions(settings: &UserSettings) -> Option<Vec<UVec2>> {
+    let scale = QUALITY_PROFILES[saved_profile(settings)].render_scale;
+    let recommended = recommended_resolution(settings)?;
+    Some(vec![(recommended.as_vec2() * scale).round().as_uvec2()])
+}
+
+#[derive(Resource)]
+pub struct RenderQualitySettings {
+    pub profile: usize,
+    // the scale the swapchain was requested with, 1.0 when the runtime chose the size
+    start_scale: f32,
+    cycle_action: Entity,
+}
+
+pub fn setup_render_quality(mut commands: Commands, settings: Res<UserSettings>) {
+    let set = commands.spawn(ActionSet::new("render_quality", "Render Quality", 1)).id();
+    let cycle_action = commands
+        .spawn((
+            Action::new("cycle_render_quality", "Cycle Render Quality", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::F2).just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    let profile = saved_profile(&settings);
+    let start_scale = if recommended_resolution(&settings).is_some() {
+        QUALITY_PROFILES[profile].render_scale
+    } else {
+        1.0
+    };
+    commands.insert_resource(RenderQualitySettings {
+        profile,
+        start_scale,
+        cycle_action,
+    });
+}
+
+// the runtime only tells the recommended size once the session exists,
+// it is kept for the render scale of the next start
+pub fn store_recommended_resolution(
+    quality: Res<RenderQualitySettings>,
+    graphics: Res<OxrGraphicsInfo>,
+    mut settings: ResMut<UserSettings>,
+) {
+    let recommended = (graphics.resolution.as_vec2() / quality.start_scale).round().as_uvec2();
+    if recommended_resolution(&settings) != Some(recommended) {
+        info!("recommended per eye size: {}", recommended);
+        settings.set("xr.recommended_width", recommended.x);
+        settings.set("xr.recommended_height", recommended.y);
+    }
+}
+
+// the key on the desktop, in the headset the tools menu
+pub fn cycle_render_quality(
+    mut quality: ResMut<RenderQualitySettings>,
+    bool_value: Query<&BoolActionValue>,
+    mut tool_input: MessageReader<ToolInput>,
+    mut settings: ResMut<UserSettings>,
+) {
+    let mut step = bool_value.get(quality.cycle_action).unwrap().any as i32;
+    for input in tool_input.read() {
+        if input.tool == Tool::RenderQuality {
+            step += input.direction;
+        }
+    }
+    if step == 0 {
+        return;
+    }
+    let count = QUALITY_PROFILES.len() as i32;
+    let previous = QUALITY_PROFILES[quality.profile].render_scale;
+    quality.profile = (quality.profile as i32 + step).rem_euclid(count) as usize;
+    let profile = &QUALITY_PROFILES[quality.profile];
+    info!("render quality: {}", profile.name);
+    if profile.render_scale != previous {
+        info!("render scale {} is used after a restart", profile.render_scale);
+    }
+    settings.set("render_quality", profile.name);
+}
+
+// msaa and indirect drawing for every camera, hdr and tonemapping only for the 3d ones,
+// the ui panels render into 8 bit textures
+pub fn apply_render_quality(
+    quality: Res<RenderQualitySettings>,
+    mut commands: Commands,
+    cameras: Query<(Entity, Has<Camera3d>), With<Camera>>,
+    new_cameras: Query<(), Added<Camera>>,
+) {
+    // xr cameras are spawned with the session
+    if !quality.is_changed() && new_cameras.is_empty() {
+        return;
+    }
+    let profile = &QUALITY_PROFILES[quality.profile];
+    for (camera, is_3d) in &cameras {
+        let mut camera = commands.entity(camera);
+        camera.insert(profile.msaa);
+        if profile.indirect_drawing {
+            camera.remove::<NoIndirectDrawing>();
+        } else {
+            camera.insert(NoIndirectDrawing);
+        }
+        if !is_3d {
+            continue;
+        }
+        camera.insert(profile.tonemapping);
+        if profile.hdr {
+            camera.insert(Hdr);
+        } else {
+            camera.remove::<Hdr>();
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use bevy::prelude::*;
+use schminput::prelude::*;
+
+use crate::{
+    capabilities::XrCapabilities,
+    haptics::{Hand, Haptics},
+    light_editor::{color_temperature, LightEditor},
+    lighting::{light_angles, light_rotation, lighting_index, ActiveLighting, SceneLight},
+    passthrough::PassthroughMode,
+    pointer::AimRight,
+    settings::UserSettings,
+    tools_menu::{Tool, ToolInput},
+    MoveActions,
+};
+
+const MAX_AMBIENT_BRIGHTNESS: f32 = 2000.0;
+// the main light follows the ambient brightness, a bright room has a bright main light
+const ILLUMINANCE_PER_BRIGHTNESS: f32 = 12.5;
+
+// lighting used for models shown over passthrough, matched to the room by hand,
+// the passthrough extension gives no access to the camera pixels to measure it
+#[derive(Resource)]
+pub struct RoomLighting {
+    pub enabled: bool,
+    // kelvin of the ambient light, white at 6500
+    pub ambient_temperature: f32,
+    pub ambient_brightness: f32,
+    // direction the main light shines to
+    pub direction: Dir3,
+    // while the match trigger is held, the left thumbstick changes the ambient light
+    pub matching: bool,
+    toggle_action: Entity,
+    match_action: Entity,
+    toggle_released: bool,
+}
+
+impl RoomLighting {
+    pub fn ambient_color(&self) -> Color {
+        color_temperature(self.ambient_temperature)
+    }
+
+    pub fn illuminance(&self) -> f32 {
+        self.ambient_brightness * ILLUMINANCE_PER_BRIGHTNESS
+    }
+}
+
+pub fn setup_room_lighting(
+    mut cmds: Commands,
+    capabilities: Res<XrCapabilities>,
+    settings: Res<UserSettings>,
+) {
+    let set = cmds.spawn(ActionSet::new("room_lighting", "Room Lighting", 1)).id();
+    let toggle_action = cmds
+        .spawn((
+            Action::new("toggle_room_lighting", "Toggle Room Lighting", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyM)),
+            BoolActionValue::new(),
+        ))
+        .id();
+    let match_action = cmds
+        .spawn((
+            Action::new("match_room_light", "Match Room Light", set),
+            capabilities.bindings("/user/hand/right/input/trigger/value"),
+            F32ActionValue::new(),
+        ))
+        .id();
+    let direction = match (
+        settings.get::<f32>("room_lighting.direction.x"),
+        settings.get::<f32>("room_lighting.direction.y"),
+        settings.get::<f32>("room_lighting.direction.z"),
+    ) {
+        (Some(x), Some(y), Some(z)) => Dir3::new(Vec3::new(x, y, z)).unwrap_or(Dir3::NEG_Y),
+        _ => Dir3::new(Vec3::new(0.3, -1.0, -0.3)).unwrap(),
+    };
+    cmds.insert_resource(RoomLighting {
+        enabled: settings.get("room_lighting.enabled").unwrap_or(true),
+        ambient_temperature: settings.get("room_lighting.temperature").unwrap_or(6500.0),
+        ambient_brightness: settings.get("room_lighting.brightness").unwrap_or(400.0),
+        direction,
+        matching: false,
+        toggle_action,
+        match_action,
+        toggle_released: true,
+    });
+}
+
+// the key on the desktop, in the headset the tools menu
+pub fn toggle_room_lighting(
+    mut room: ResMut<RoomLighting>,
+    bool_value: Query<&BoolActionValue>,
+    mut tool_input: MessageReader<ToolInput>,
+    mut settings: ResMut<UserSettings>,
+) {
+    let from_menu = tool_input
+        .read()
+        .filter(|input| input.tool == Tool::RoomLighting)
+        .count()
+        % 2
+        == 1;
+    let pressed = bool_value.get(room.toggle_action).unwrap().any;
+    if !pressed {
+        room.bypass_change_detection().toggle_released = true;
+    }
+    let from_key = pressed && room.toggle_released;
+    if from_key {
+        room.toggle_released = false;
+    }
+    if !from_menu && !from_key {
+        return;
+    }
+    room.enabled = !room.enabled;
+    info!("room lighting: {}", room.enabled);
+    settings.set("room_lighting.enabled", room.enabled);
+}
+
+// manual room matching: point the right laser at the main light of the room and pull the trigger,
+// while holding it the left thumbstick matches the model to the room by eye,
+// up/down ambient brightness, left/right warm or cold light
+pub fn match_room_light(
+    mut room: ResMut<RoomLighting>,
+    passthrough: Res<PassthroughMode>,
+    editor: Res<LightEditor>,
+    move_actions: Res<MoveActions>,
+    f32_value: Query<&F32ActionValue>,
+    vec2_value: Query<&Vec2ActionValue>,
+    aim: Query<&GlobalTransform, With<AimRight>>,
+    time: Res<Time>,
+    mut settings: ResMut<UserSettings>,
+    mut haptics: MessageWriter<Haptics>,
+) {
+    // the light editor uses the same trigger
+    let pulled = f32_value.get(room.match_action).unwrap().any > 0.5;
+    if !room.enabled || !passthrough.enabled || editor.active || !pulled {
+        if room.matching {
+            room.matching = false;
+            // only persist when done, not on every small thumbstick step
+            info!(
+                "room ambient light: {:.0} K, brightness {:.0}",
+                room.ambient_temperature, room.ambient_brightness
+            );
+            settings.set("room_lighting.temperature", room.ambient_temperature);
+            settings.set("room_lighting.brightness", room.ambient_brightness);
+        }
+        return;
+    }
+    if !room.matching {
+        room.matching = true;
+        let Ok(aim) = aim.single() else {
+            return;
+        };
+        // the light comes from where we point
+        room.direction = -aim.forward();
+        info!("room light direction: {:?}", room.direction);
+        settings.set("room_lighting.direction.x", room.direction.x);
+        settings.set("room_lighting.direction.y", room.direction.y);
+        settings.set("room_lighting.direction.z", room.direction.z);
+        haptics.write(Haptics::pulse(Hand::Right));
+    }
+    let stick = vec2_value.get(move_actions.move_action).unwrap().any;
+    if stick.y.abs() > 0.2 {
+        let factor = (stick.y * time.delta_secs() * 2.0).exp();
+        room.ambient_brightness =
+            (room.ambient_brightness * factor).clamp(10.0, MAX_AMBIENT_BRIGHTNESS);
+    }
+    if stick.x.abs() > 0.2 {
+        room.ambient_temperature = (room.ambient_temperature
+            + stick.x * time.delta_secs() * 3000.0)
+            .clamp(1500.0, 12000.0);
+    }
+}
+
+pub fn apply_room_lighting(
+    room: Res<RoomLighting>,
+    active: Res<ActiveLighting>,
+    mut ambient: ResMut<GlobalAmbientLight>,
+    mut lights: Query<(&mut SceneLight, &mut DirectionalLight, &mut Transform)>,
+) {
+    if Some(active.0) != lighting_index("room") {
+        return;
+    }
+    if !room.is_changed() && !active.is_changed() {
+        return;
+    }
+    ambient.color = room.ambient_color();
+    ambient.brightness = room.ambient_brightness;
+    let (azimuth, elevation) = light_angles(room.direction);
+    for (mut light, mut directional, mut transform) in &mut lights {
+        light.azimuth = azimuth;
+        light.elevation = elevation;
+        directional.illuminance = room.illuminance();
+        transform.rotation = light_rotation(azimuth, elevation);
+    }
+}
@@ -0,0 +1,95 @@
+use std::{collections::BTreeMap, fs, path::PathBuf, str::FromStr};
+
+use bevy::prelude::*;
+
+const SETTINGS_FILE: &str = "settings.txt";
+
+// where we are allowed to write files, the app folder on android
+pub fn data_dir() -> PathBuf {
+    #[cfg(target_os = "android")]
+    if let Some(path) = bevy::android::ANDROID_APP
+        .get()
+        .and_then(|app| app.internal_data_path())
+    {
+        return path;
+    }
+    PathBuf::from(".")
+}
+
+// simple "key = value" lines, saved whenever a value changes
+#[derive(Resource, Default)]
+pub struct UserSettings {
+    values: BTreeMap<String, String>,
+}
+
+impl UserSettings {
+    pub fn load() -> Self {
+        let path = data_dir().join(SETTINGS_FILE);
+        fs::read_to_string(&path)
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    fn parse(content: &str) -> Self {
+        let values = content
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect();
+        Self { values }
+    }
+
+    fn to_text(&self) -> String {
+        self.values
+            .iter()
+            .map(|(key, value)| format!("{} = {}\n", key, value))
+            .collect()
+    }
+
+    pub fn get<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.values.get(key).and_then(|value| value.parse().ok())
+    }
+
+    pub fn set(&mut self, key: &str, value: impl ToString) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+
+    fn save(&self) {
+        let path = data_dir().join(SETTINGS_FILE);
+        if let Err(err) = fs::write(&path, self.to_text()) {
+            warn!("unable to save settings to {}: {}", path.display(), err);
+        }
+    }
+}
+
+pub fn save_settings(settings: Res<UserSettings>) {
+    if settings.is_changed() && !settings.is_added() {
+        settings.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_key_value_lines() {
+        let settings = UserSettings::parse("a.b = 1.5\nname=quest \n\nno value line\nflag = true\n");
+        assert_eq!(settings.get::<f32>("a.b"), Some(1.5));
+        assert_eq!(settings.get::<String>("name"), Some("quest".to_string()));
+        assert_eq!(settings.get::<bool>("flag"), Some(true));
+        assert_eq!(settings.get::<f32>("name"), None);
+        assert_eq!(settings.get::<bool>("missing"), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut settings = UserSettings::default();
+        settings.set("passthrough.brightness", 0.25);
+        settings.set("render_quality", "desktop");
+        settings.set("audio.muted", false);
+        let loaded = UserSettings::parse(&settings.to_text());
+        assert_eq!(loaded.values, settings.values);
+        assert_eq!(loaded.get::<f32>("passthrough.brightness"), Some(0.25));
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use bevy::{audio::Volume, prelude::*};
+
+use crate::{
+    haptics::Hand, settings::UserSettings, spatial_audio::HeadListener, HandLeft, HandRight,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SfxEvent {
+    SceneSwitch,
+    SnapTurn,
+    Grab,
+    Release,
+    ResetModel,
+}
+
+impl SfxEvent {
+    const ALL: [SfxEvent; 5] = [
+        SfxEvent::SceneSwitch,
+        SfxEvent::SnapTurn,
+        SfxEvent::Grab,
+        SfxEvent::Release,
+        SfxEvent::ResetModel,
+    ];
+
+    // used for the user settings, e.g. "sfx.snap_turn = whoosh.ogg"
+    fn key(self) -> &'static str {
+        match self {
+            SfxEvent::SceneSwitch => "scene_switch",
+            SfxEvent::SnapTurn => "snap_turn",
+            SfxEvent::Grab => "grab",
+            SfxEvent::Release => "release",
+            SfxEvent::ResetModel => "reset_model",
+        }
+    }
+}
+
+pub struct SfxSound {
+    pub event: SfxEvent,
+    pub file_name: &'static str,
+    pub volume: f32,
+    // playback speed, also changes the pitch
+    pub speed: f32,
+}
+
+// defaults, each can be replaced in the user settings
+pub static SFX_SOUNDS: &[SfxSound] = &[
+    SfxSound {
+        event: SfxEvent::SceneSwitch,
+        file_name: "laser.wav",
+        volume: 0.4,
+        speed: 2.0,
+    },
+    SfxSound {
+        event: SfxEvent::SnapTurn,
+        file_name: "laser.ogg",
+        volume: 0.3,
+        speed: 0.5,
+    },
+    SfxSound {
+        event: SfxEvent::Grab,
+        file_name: "laser.wav",
+        volume: 0.3,
+        speed: 1.5,
+    },
+    SfxSound {
+        event: SfxEvent::Release,
+        file_name: "laser.wav",
+        volume: 0.3,
+        speed: 1.0,
+    },
+    SfxSound {
+        event: SfxEvent::ResetModel,
+        file_name: "laser.ogg",
+        volume: 0.4,
+        speed: 1.2,
+    },
+];
+
+#[derive(Clone, Copy, Debug)]
+pub enum SfxPosition {
+    Hand(Hand),
+    Head,
+    Point(Vec3),
+}
+
+// send this message to play the sound of an interaction
+#[derive(Message, Clone, Copy, Debug)]
+pub struct Sfx {
+    pub event: SfxEvent,
+    pub at: SfxPosition,
+}
+
+impl Sfx {
+    pub fn new(event: SfxEvent, at: SfxPosition) -> Self {
+        Self { event, at }
+    }
+}
+
+struct LoadedSfx {
+    source: Handle<AudioSource>,
+    settings: PlaybackSettings,
+}
+
+#[derive(Resource)]
+pub struct SfxLibrary {
+    sounds: HashMap<SfxEvent, LoadedSfx>,
+}
+
+pub fn setup_sfx_library(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<UserSettings>,
+) {
+    let mut sounds = HashMap::new();
+    for event in SfxEvent::ALL {
+        let key = event.key();
+        let default = SFX_SOUNDS.iter().find(|sound| sound.event == event);
+        // an empty file name in the settings turns the sound off
+        let Some(file_name) = settings
+            .get::<String>(&format!("sfx.{}", key))
+            .or(default.map(|sound| sound.file_name.to_string()))
+            .filter(|file_name| !file_name.is_empty())
+        else {
+            continue;
+        };
+        let volume = settings
+            .get(&format!("sfx.{}.volume", key))
+            .or(default.map(|sound| sound.volume))
+            .unwrap_or(1.0);
+        let speed = settings
+            .get(&format!("sfx.{}.speed", key))
+            .or(default.map(|sound| sound.speed))
+            .unwrap_or(1.0);
+        sounds.insert(
+            event,
+            LoadedSfx {
+                source: asset_server.load(file_name),
+                settings: PlaybackSettings::DESPAWN
+                    .with_spatial(true)
+                    .with_volume(Volume::Linear(volume))
+                    .with_speed(speed),
+            },
+        );
+    }
+    commands.insert_resource(SfxLibrary { sounds });
+}
+
+pub fn play_sfx(
+    mut commands: Commands,
+    library: Res<SfxLibrary>,
+    mut messages: MessageReader<Sfx>,
+    left_hand: Query<&GlobalTransform, With<HandLeft>>,
+    right_hand: Query<&GlobalTransform, With<HandRight>>,
+    head: Query<&GlobalTransform, With<HeadListener>>,
+) {
+    for sfx in messages.read() {
+        let Some(sound) = library.sounds.get(&sfx.event) else {
+            continue;
+        };
+        let position = match sfx.at {
+            SfxPosition::Hand(Hand::Left) => left_hand.single().map(|hand| hand.translation()),
+            SfxPosition::Hand(Hand::Right) => right_hand.single().map(|hand| hand.translation()),
+            SfxPosition::Head => head.single().map(|head| head.translation()),
+            SfxPosition::Point(point) => Ok(point),
+        };
+        commands.spawn((
+            AudioPlayer::new(sound.source.clone()),
+            sound.settings,
+            Transform::from_translation(position.unwrap_or_default()),
+        ));
+    }
+}
@@ -0,0 +1,129 @@
+use bevy::{
+    light::NotShadowCaster, prelude::*, render::render_resource::AsBindGroup, shader::ShaderRef,
+};
+use bevy_mod_openxr::session::OxrSession;
+use bevy_mod_xr::session::XrTrackingRoot;
+
+use crate::{passthrough::PassthroughMode, settings::UserSettings};
+
+const SHADER_ASSET_PATH: &str = "shaders/shadow_catcher.wgsl";
+// keeps the plane from fighting with models standing on the floor
+const FLOOR_OFFSET: f32 = 0.001;
+
+// transparent material that only shows the shadows it receives
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct ShadowCatcherMaterial {
+    // a is the strength of the shadow
+    #[uniform(0)]
+    shadow_color: LinearRgba,
+    // xyz where the model touches the floor, w radius of the contact shadow
+    #[uniform(0)]
+    contact: Vec4,
+    // strength of the contact shadow, 0.0 turns it off
+    #[uniform(0)]
+    ambient_occlusion: f32,
+}
+
+impl Material for ShadowCatcherMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_ASSET_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+}
+
+// invisible floor in passthrough mode, so models cast their shadows onto the real floor
+#[derive(Component)]
+pub struct ShadowCatcher;
+
+// a reference space with its origin on the real floor, located relative to the tracking root
+#[derive(Component)]
+pub struct FloorSpace;
+
+// the stage space covers the guardian area, local floor is there in seated setups too
+pub fn create_floor_space(session: Res<OxrSession>, mut commands: Commands) {
+    let supported = session.enumerate_reference_spaces().unwrap_or_default();
+    let Some(space_type) = [
+        openxr::ReferenceSpaceType::STAGE,
+        openxr::ReferenceSpaceType::LOCAL_FLOOR,
+    ]
+    .into_iter()
+    .find(|space_type| supported.contains(space_type)) else {
+        warn!("no floor reference space, the shadow catcher uses the tracking root height");
+        return;
+    };
+    match session.create_reference_space(space_type, Isometry3d::IDENTITY) {
+        Ok(space) => {
+            info!("shadow catcher floor from {:?}", space_type);
+            commands.spawn((FloorSpace, space.0));
+        }
+        Err(err) => warn!("unable to create the floor space: {}", err),
+    }
+}
+
+pub fn setup_shadow_catcher(
+    mut commands: Commands,
+    settings: Res<UserSettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ShadowCatcherMaterial>>,
+) {
+    let strength = settings.get("shadow_catcher.strength").unwrap_or(0.6);
+    let ambient_occlusion = settings.get("shadow_catcher.ambient_occlusion").unwrap_or(0.5);
+    commands.spawn((
+        ShadowCatcher,
+        Mesh3d(meshes.add(Plane3d::default().mesh().size(10.0, 10.0))),
+        MeshMaterial3d(materials.add(ShadowCatcherMaterial {
+            shadow_color: LinearRgba::BLACK.with_alpha(strength),
+            contact: Vec4::new(0.0, 0.0, 0.0, 0.4),
+            ambient_occlusion,
+        })),
+        NotShadowCaster,
+        Transform::default(),
+        Visibility::Hidden,
+    ));
+}
+
+// follows the shown model at the height of the real floor
+pub fn update_shadow_catcher(
+    passthrough: Res<PassthroughMode>,
+    root: Query<&GlobalTransform, With<XrTrackingRoot>>,
+    floor_space: Query<&Transform, With<FloorSpace>>,
+    models: Query<&GlobalTransform, With<SceneRoot>>,
+    mut catcher: Query<
+        (&mut Transform, &mut Visibility, &MeshMaterial3d<ShadowCatcherMaterial>),
+        With<ShadowCatcher>,
+    >,
+    mut materials: ResMut<Assets<ShadowCatcherMaterial>>,
+) {
+    let Ok((mut transform, mut visibility, material)) = catcher.single_mut() else {
+        return;
+    };
+    visibility.set_if_neq(if passthrough.enabled {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    });
+    if !passthrough.enabled {
+        return;
+    }
+    // the floor space pose is relative to the tracking root, without it the root is on the floor
+    let floor = match (root.single(), floor_space.single()) {
+        (Ok(root), Ok(floor)) => root.transform_point(floor.translation).y,
+        (Ok(root), Err(_)) => root.translation().y,
+        _ => 0.0,
+    };
+    let Some(model) = models.iter().next() else {
+        return;
+    };
+    let (scale, _, position) = model.to_scale_rotation_translation();
+    transform.translation = Vec3::new(position.x, floor + FLOOR_OFFSET, position.z);
+    let contact = position.with_y(floor).extend(0.4 * scale.max_element());
+    // only touch the asset when the model moved, every change uploads it again
+    if materials.get(&material.0).is_some_and(|material| material.contact != contact)
+        && let Some(mut material) = materials.get_mut(&material.0)
+    {
+        material.contact = contact;
+    }
+}
@@ -0,0 +1,380 @@
+use bevy::{color::palettes::css, mesh::skinning::SkinnedMesh, prelude::*};
+use bevy_mod_xr::session::XrTrackingRoot;
+use schminput::prelude::*;
+
+use crate::{
+    haptics::Hand,
+    panel::{panel_text, set_panel_visible, spawn_world_panel, WorldPanel},
+    pointer::LaserPointers,
+    settings::UserSettings,
+    tools_menu::{Tool, ToolInput},
+    HandLeft, HandRight, HeadsetView,
+};
+
+// the panel text is rebuilt a few times per second, the joints move with the animation
+const PANEL_INTERVAL: f32 = 0.2;
+// more joints than this are cut off in the panel list
+const MAX_LISTED_JOINTS: usize = 24;
+const JOINT_RADIUS: f32 = 0.01;
+const AXES_LENGTH: f32 = 0.05;
+// the joint label floats this far above and in front of the joint, outside of the mesh
+const LABEL_OFFSET: f32 = 0.04;
+
+// drawn on top of the model, the bones are inside the mesh
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct SkeletonGizmos;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JointAxes {
+    Off,
+    // the joint rotation
+    Local,
+    // world x/y/z at the joint position
+    World,
+}
+
+impl JointAxes {
+    pub fn name(self) -> &'static str {
+        match self {
+            JointAxes::Off => "off",
+            JointAxes::Local => "local",
+            JointAxes::World => "world",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [JointAxes::Off, JointAxes::Local, JointAxes::World]
+            .into_iter()
+            .find(|axes| axes.name() == name)
+    }
+
+    fn next(self) -> Self {
+        match self {
+            JointAxes::Off => JointAxes::Local,
+            JointAxes::Local => JointAxes::World,
+            JointAxes::World => JointAxes::Off,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct SkeletonView {
+    pub visible: bool,
+    pub axes: JointAxes,
+    // the joint nearest to the right controller
+    pub highlighted: Option<Entity>,
+    toggle_action: Entity,
+    axes_action: Entity,
+    timer: Timer,
+    panel: Option<WorldPanel>,
+    text: Option<Entity>,
+    // the name of the highlighted joint, shown at the joint
+    label: Option<WorldPanel>,
+    label_text: Option<Entity>,
+    labeled_joint: Option<Entity>,
+}
+
+pub fn setup_skeleton_view(
+    mut commands: Commands,
+    settings: Res<UserSettings>,
+    mut config_store: ResMut<GizmoConfigStore>,
+) {
+    let set = commands.spawn(ActionSet::new("skeleton", "Skeleton", 1)).id();
+    let toggle_action = commands
+        .spawn((
+            Action::new("toggle_skeleton", "Toggle Skeleton", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyB).just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    let axes_action = commands
+        .spawn((
+            Action::new("cycle_joint_axes", "Cycle Joint Axes", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyH).just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    commands.insert_resource(SkeletonView {
+        visible: settings.get("skeleton.visible").unwrap_or(false),
+        axes: settings
+            .get::<String>("skeleton.axes")
+            .and_then(|name| JointAxes::from_name(&name))
+            .unwrap_or(JointAxes::Local),
+        highlighted: None,
+        toggle_action,
+        axes_action,
+        timer: Timer::from_seconds(PANEL_INTERVAL, TimerMode::Repeating),
+        panel: None,
+        text: None,
+        label: None,
+        label_text: None,
+        labeled_joint: None,
+    });
+    let (config, _) = config_store.config_mut::<SkeletonGizmos>();
+    config.depth_bias = -1.0;
+}
+
+// the keys on the desktop, in the headset the tools menu
+pub fn control_skeleton_view(
+    mut view: ResMut<SkeletonView>,
+    bool_value: Query<&BoolActionValue>,
+    mut tool_input: MessageReader<ToolInput>,
+    mut settings: ResMut<UserSettings>,
+) {
+    let mut toggle = bool_value.get(view.toggle_action).unwrap().any;
+    let mut cycle_axes = bool_value.get(view.axes_action).unwrap().any;
+    for input in tool_input.read() {
+        match input.tool {
+            Tool::Skeleton => toggle = !toggle,
+            Tool::JointAxes => cycle_axes = true,
+            _ => {}
+        }
+    }
+    if toggle {
+        view.visible = !view.visible;
+        info!("skeleton: {}", view.visible);
+        settings.set("skeleton.visible", view.visible);
+    }
+    if cycle_axes {
+        view.axes = view.axes.next();
+        info!("joint axes: {}", view.axes.name());
+        settings.set("skeleton.axes", view.axes.name());
+    }
+}
+
+// the joints of every skinned mesh in the shown scenes, parents before children like in the gltf
+fn scene_joints(
+    models: &Query<Entity, With<SceneRoot>>,
+    children: &Query<&Children>,
+    skinned_meshes: &Query<&SkinnedMesh>,
+) -> Vec<Entity> {
+    let mut joints = vec![];
+    for entity in models.iter().flat_map(|model| children.iter_descendants(model)) {
+        if let Ok(skinned_mesh) = skinned_meshes.get(entity) {
+            for joint in &skinned_mesh.joints {
+                if !joints.contains(joint) {
+                    joints.push(*joint);
+                }
+            }
+        }
+    }
+    joints
+}
+
+pub fn draw_skeleton(
+    mut view: ResMut<SkeletonView>,
+    mut gizmos: Gizmos<SkeletonGizmos>,
+    lasers: Res<LaserPointers>,
+    right_hand: Query<&GlobalTransform, With<HandRight>>,
+    models: Query<Entity, With<SceneRoot>>,
+    children: Query<&Children>,
+    skinned_meshes: Query<&SkinnedMesh>,
+    joint_transforms: Query<(&GlobalTransform, Option<&ChildOf>)>,
+) {
+    // only the toggles count as a change for the panel
+    let view = view.bypass_change_detection();
+    if !view.visible {
+        view.highlighted = None;
+        return;
+    }
+    let joints = scene_joints(&models, &children, &skinned_meshes);
+    // pointing at the model beats the grip position, which is meaningless on the desktop
+    let target = lasers
+        .hit(Hand::Right)
+        .map(|hit| hit.point)
+        .or(right_hand.single().ok().map(|hand| hand.translation()));
+    let highlighted = target.and_then(|target| {
+        joints
+            .iter()
+            .filter_map(|joint| joint_transforms.get(*joint).ok().map(|(global, _)| (*joint, global)))
+            .min_by(|(_, a), (_, b)| {
+                a.translation()
+                    .distance_squared(target)
+                    .total_cmp(&b.translation().distance_squared(target))
+            })
+            .map(|(joint, _)| joint)
+    });
+    view.highlighted = highlighted;
+
+    for joint in &joints {
+        let Ok((global, parent)) = joint_transforms.get(*joint) else {
+            continue;
+        };
+        let position = global.translation();
+        let is_highlighted = highlighted == Some(*joint);
+        let color = if is_highlighted { css::YELLOW } else { css::AQUA };
+        gizmos.sphere(
+            Isometry3d::from_translation(position),
+            if is_highlighted { JOINT_RADIUS * 2.0 } else { JOINT_RADIUS },
+            color,
+        );
+        if let Some(parent) = parent
+            && joints.contains(&parent.parent())
+            && let Ok((parent_global, _)) = joint_transforms.get(parent.parent())
+        {
+            gizmos.line(parent_global.translation(), position, css::AQUA);
+        }
+        match view.axes {
+            JointAxes::Off => {}
+            JointAxes::Local => gizmos.axes(*global, AXES_LENGTH),
+            JointAxes::World => gizmos.axes(Transform::from_translation(position), AXES_LENGTH),
+        }
+    }
+}
+
+// the name of the highlighted joint next to it, turned towards the viewer
+pub fn update_joint_label(
+    mut commands: Commands,
+    mut view: ResMut<SkeletonView>,
+    joints_info: Query<(&GlobalTransform, Option<&Name>)>,
+    root: Query<&Transform, (With<XrTrackingRoot>, Without<HeadsetView>)>,
+    headset: Query<&Transform, (With<HeadsetView>, Without<XrTrackingRoot>)>,
+    mut transforms: Query<&mut Transform, (Without<XrTrackingRoot>, Without<HeadsetView>)>,
+    mut texts: Query<&mut Text>,
+    mut cameras: Query<&mut Camera>,
+    targets: Query<&UiTargetCamera>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let view = view.bypass_change_detection();
+    let joint = view.highlighted.filter(|_| view.visible);
+    let Some(label) = &view.label else {
+        if joint.is_some() {
+            let label = spawn_world_panel(
+                &mut commands,
+                &mut images,
+                &mut meshes,
+                &mut materials,
+                UVec2::new(256, 48),
+                0.12,
+            );
+            let text = commands.spawn(panel_text("", 22.0)).id();
+            commands.entity(label.root).add_child(text);
+            view.label_text = Some(text);
+            view.label = Some(label);
+        }
+        // the quad is spawned with the commands, place it next frame
+        return;
+    };
+    if joint != view.labeled_joint {
+        set_panel_visible(&mut commands, label, &mut cameras, &targets, joint.is_some());
+        if let Some(joint) = joint
+            && let Some(mut text) = view.label_text.and_then(|text| texts.get_mut(text).ok())
+        {
+            let name = joints_info.get(joint).ok().and_then(|(_, name)| name);
+            text.0 = name.map_or_else(|| format!("{}", joint), |name| name.to_string());
+        }
+        view.labeled_joint = joint;
+    }
+    let Some((global, _)) = joint.and_then(|joint| joints_info.get(joint).ok()) else {
+        return;
+    };
+    // the headset pose is relative to the tracking root, on the desktop there is neither
+    let head = match (root.single(), headset.single()) {
+        (Ok(root), Ok(headset)) => root.mul_transform(*headset),
+        _ => Transform::from_xyz(0.0, 1.5, 1.0),
+    };
+    let position = global.translation();
+    let to_head = (head.translation - position).normalize_or(Vec3::Z);
+    if let Ok(mut transform) = transforms.get_mut(label.quad) {
+        // the quad shows its front along +z
+        *transform = Transform::from_translation(position + (to_head + Vec3::Y) * LABEL_OFFSET)
+            .looking_to(-to_head, Vec3::Y);
+    }
+}
+
+// the joint hierarchy above the left controller, the highlighted joint marked and detailed
+pub fn update_skeleton_panel(
+    mut commands: Commands,
+    mut view: ResMut<SkeletonView>,
+    time: Res<Time>,
+    left_hand: Query<Entity, With<HandLeft>>,
+    models: Query<Entity, With<SceneRoot>>,
+    children: Query<&Children>,
+    skinned_meshes: Query<&SkinnedMesh>,
+    joints_info: Query<(&Transform, &GlobalTransform, Option<&Name>, Option<&ChildOf>)>,
+    parents: Query<&ChildOf>,
+    mut texts: Query<&mut Text>,
+    mut cameras: Query<&mut Camera>,
+    targets: Query<&UiTargetCamera>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if view.is_changed() {
+        if let Some(panel) = &view.panel {
+            set_panel_visible(&mut commands, panel, &mut cameras, &targets, view.visible);
+        } else if view.visible {
+            let panel = spawn_world_panel(
+                &mut commands,
+                &mut images,
+                &mut meshes,
+                &mut materials,
+                UVec2::new(512, 768),
+                0.2,
+            );
+            commands.entity(panel.quad).insert(
+                // above the passthrough style menu
+                Transform::from_xyz(0.0, 0.35, -0.05).with_rotation(Quat::from_rotation_x(-0.5)),
+            );
+            if let Ok(hand) = left_hand.single() {
+                commands.entity(hand).add_child(panel.quad);
+            }
+            let text = commands.spawn(panel_text("", 18.0)).id();
+            commands.entity(panel.root).add_child(text);
+            view.text = Some(text);
+            view.panel = Some(panel);
+        }
+    }
+    let view = view.bypass_change_detection();
+    if !view.visible || !view.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Some(mut text) = view.text.and_then(|text| texts.get_mut(text).ok()) else {
+        return;
+    };
+    let joints = scene_joints(&models, &children, &skinned_meshes);
+    let name = |joint: Entity| {
+        joints_info
+            .get(joint)
+            .ok()
+            .and_then(|(_, _, name, _)| name)
+            .map_or_else(|| format!("{}", joint), |name| name.to_string())
+    };
+    let mut lines = vec![format!("{} joints, axes {}", joints.len(), view.axes.name())];
+    if let Some(joint) = view.highlighted
+        && let Ok((local, global, _, parent)) = joints_info.get(joint)
+    {
+        let (x, y, z) = local.rotation.to_euler(EulerRot::XYZ);
+        let world = global.translation();
+        lines.push(format!("> {}", name(joint)));
+        if let Some(parent) = parent {
+            lines.push(format!("  parent {}", name(parent.parent())));
+        }
+        lines.push(format!(
+            "  local {:.3} {:.3} {:.3}, rot {:.0} {:.0} {:.0}",
+            local.translation.x,
+            local.translation.y,
+            local.translation.z,
+            x.to_degrees(),
+            y.to_degrees(),
+            z.to_degrees()
+        ));
+        lines.push(format!("  world {:.3} {:.3} {:.3}", world.x, world.y, world.z));
+    }
+    lines.push(String::new());
+    for joint in joints.iter().take(MAX_LISTED_JOINTS) {
+        // indented by the number of joints above it
+        let depth = parents
+            .iter_ancestors(*joint)
+            .filter(|ancestor| joints.contains(ancestor))
+            .count();
+        let marker = if view.highlighted == Some(*joint) { ">" } else { " " };
+        lines.push(format!("{}{}{}", marker, "  ".repeat(depth), name(*joint)));
+    }
+    if joints.len() > MAX_LISTED_JOINTS {
+        lines.push(format!("  ... {} more", joints.len() - MAX_LISTED_JOINTS));
+    }
+    text.0 = lines.join("\n");
+}
@@ -0,0 +1,82 @@
+use bevy::{audio::Volume, prelude::*, scene::SceneInstanceReady};
+use bevy_mod_xr::session::XrTrackingRoot;
+
+use crate::{asset_handler::ASSET_ELEMENTS, HeadsetView, MoveActions};
+
+// distance between the ears in meters
+const EAR_GAP: f32 = 0.16;
+
+// the ears of the player, follow the headset
+#[derive(Component)]
+pub struct HeadListener;
+
+// looping sound of the shown model, despawned with it
+#[derive(Component)]
+pub struct ModelSoundEmitter;
+
+pub fn setup_listener(mut commands: Commands) {
+    commands.spawn((HeadListener, SpatialListener::new(EAR_GAP), Transform::default()));
+}
+
+// the headset pose is relative to the tracking root, without a headset the desktop camera is used
+pub fn follow_headset(
+    root: Query<&Transform, (With<XrTrackingRoot>, Without<HeadListener>)>,
+    headset: Query<&Transform, (With<HeadsetView>, Without<HeadListener>)>,
+    camera: Query<&GlobalTransform, With<Camera3d>>,
+    mut listener: Query<&mut Transform, With<HeadListener>>,
+) {
+    let Ok(mut listener) = listener.single_mut() else {
+        return;
+    };
+    let pose = match (root.single(), headset.single()) {
+        (Ok(root), Ok(headset)) => root.mul_transform(*headset),
+        _ => match camera.iter().next() {
+            Some(camera) => camera.compute_transform(),
+            None => return,
+        },
+    };
+    listener.translation = pose.translation;
+    listener.rotation = pose.rotation;
+}
+
+// starts the sounds of the catalog entry once its scene is spawned,
+// sounds on a named node move with the animation
+pub fn attach_model_sounds(
+    trigger: On<SceneInstanceReady>,
+    mut commands: Commands,
+    move_actions: Res<MoveActions>,
+    asset_server: Res<AssetServer>,
+    models: Query<(), With<SceneRoot>>,
+    children: Query<&Children>,
+    names: Query<&Name>,
+) {
+    if models.get(trigger.entity).is_err() {
+        return;
+    }
+    let Some(asset) = ASSET_ELEMENTS.get(move_actions.shown_scene) else {
+        return;
+    };
+    for sound in asset.sounds {
+        let parent = match sound.node {
+            Some(node) => {
+                let found = children
+                    .iter_descendants(trigger.entity)
+                    .find(|child| names.get(*child).is_ok_and(|name| name.as_str() == node));
+                let Some(found) = found else {
+                    warn!("node {} not found in {}, sound {} not played", node, asset.file_name, sound.file_name);
+                    continue;
+                };
+                found
+            }
+            None => trigger.entity,
+        };
+        commands.entity(parent).with_child((
+            ModelSoundEmitter,
+            AudioPlayer::new(asset_server.load(sound.file_name)),
+            PlaybackSettings::LOOP
+                .with_spatial(true)
+                .with_volume(Volume::Linear(sound.volume)),
+            Transform::default(),
+        ));
+    }
+}
@@ -0,0 +1,212 @@
+use bevy::{
+    camera::{visibility::RenderLayers, RenderTarget},
+    prelude::*,
+    window::WindowRef,
+};
+use bevy_mod_xr::session::XrTrackingRoot;
+use schminput::prelude::*;
+
+use crate::{settings::UserSettings, HeadsetView};
+
+// only the spectator camera renders this layer, so the avatar doesn't block the headset view
+const AVATAR_LAYER: usize = 1;
+// how far behind and above the player the third person camera floats
+const FOLLOW_DISTANCE: f32 = 1.5;
+const FOLLOW_HEIGHT: f32 = 0.5;
+// higher is snappier, lower hides the head shake
+const SMOOTHING: f32 = 6.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpectatorMode {
+    Off,
+    // roughly what the headset user sees
+    Mirror,
+    // follows the player from behind, with an avatar for the headset
+    ThirdPerson,
+}
+
+impl SpectatorMode {
+    fn name(self) -> &'static str {
+        match self {
+            SpectatorMode::Off => "off",
+            SpectatorMode::Mirror => "mirror",
+            SpectatorMode::ThirdPerson => "third_person",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [SpectatorMode::Off, SpectatorMode::Mirror, SpectatorMode::ThirdPerson]
+            .into_iter()
+            .find(|mode| mode.name() == name)
+    }
+
+    fn next(self) -> Self {
+        match self {
+            SpectatorMode::Off => SpectatorMode::Mirror,
+            SpectatorMode::Mirror => SpectatorMode::ThirdPerson,
+            SpectatorMode::ThirdPerson => SpectatorMode::Off,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Spectator {
+    pub mode: SpectatorMode,
+    cycle_action: Entity,
+    window: Option<Entity>,
+    camera: Option<Entity>,
+}
+
+#[derive(Component)]
+pub struct SpectatorCamera;
+
+#[derive(Component)]
+pub struct HeadsetAvatar;
+
+pub fn setup_spectator(
+    mut commands: Commands,
+    settings: Res<UserSettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let set = commands.spawn(ActionSet::new("spectator", "Spectator", 1)).id();
+    let cycle_action = commands
+        .spawn((
+            Action::new("cycle_spectator", "Cycle Spectator View", set),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyC).just_pressed()),
+            BoolActionValue::new(),
+        ))
+        .id();
+    commands.insert_resource(Spectator {
+        mode: settings
+            .get::<String>("spectator")
+            .and_then(|name| SpectatorMode::from_name(&name))
+            .unwrap_or(SpectatorMode::Off),
+        cycle_action,
+        window: None,
+        camera: None,
+    });
+    // a visor with a strap, the front looks along -Z like the headset
+    let material = materials.add(Color::srgb(0.15, 0.15, 0.18));
+    commands.spawn((
+        HeadsetAvatar,
+        Mesh3d(meshes.add(Cuboid::new(0.18, 0.1, 0.1))),
+        MeshMaterial3d(material.clone()),
+        Transform::default(),
+        Visibility::Hidden,
+        RenderLayers::layer(AVATAR_LAYER),
+        children![(
+            Mesh3d(meshes.add(Cuboid::new(0.2, 0.03, 0.2))),
+            MeshMaterial3d(material),
+            Transform::from_xyz(0.0, 0.0, 0.1),
+            RenderLayers::layer(AVATAR_LAYER),
+        )],
+    ));
+}
+
+pub fn cycle_spectator(
+    mut spectator: ResMut<Spectator>,
+    bool_value: Query<&BoolActionValue>,
+    mut settings: ResMut<UserSettings>,
+) {
+    if !bool_value.get(spectator.cycle_action).unwrap().any {
+        return;
+    }
+    spectator.mode = spectator.mode.next();
+    info!("spectator view: {}", spectator.mode.name());
+    settings.set("spectator", spectator.mode.name());
+}
+
+// opens or closes the second window, closing it by hand turns the spectator off
+pub fn manage_spectator_window(
+    mut commands: Commands,
+    mut spectator: ResMut<Spectator>,
+    windows: Query<(), With<Window>>,
+) {
+    if let Some(window) = spectator.window
+        && windows.get(window).is_err()
+    {
+        info!("spectator window closed");
+        spectator.window = None;
+        spectator.mode = SpectatorMode::Off;
+    }
+    match (spectator.mode, spectator.window) {
+        (SpectatorMode::Off, window) => {
+            if let Some(window) = window {
+                commands.entity(window).despawn();
+            }
+            if let Some(camera) = spectator.camera.take() {
+                commands.entity(camera).despawn();
+            }
+            spectator.window = None;
+        }
+        (SpectatorMode::Mirror | SpectatorMode::ThirdPerson, None) => {
+            let window = commands
+                .spawn(Window {
+                    title: "Spectator".to_string(),
+                    resolution: (1280, 720).into(),
+                    ..default()
+                })
+                .id();
+            let camera = commands
+                .spawn((
+                    SpectatorCamera,
+                    Camera3d::default(),
+                    RenderTarget::Window(WindowRef::Entity(window)),
+                    RenderLayers::from_layers(&[0, AVATAR_LAYER]),
+                    Transform::default(),
+                ))
+                .id();
+            spectator.window = Some(window);
+            spectator.camera = Some(camera);
+        }
+        _ => {}
+    }
+}
+
+// the headset pose is relative to the tracking root
+pub fn follow_player(
+    spectator: Res<Spectator>,
+    time: Res<Time>,
+    root: Query<&Transform, (With<XrTrackingRoot>, Without<SpectatorCamera>, Without<HeadsetAvatar>)>,
+    headset: Query<&Transform, (With<HeadsetView>, Without<SpectatorCamera>, Without<HeadsetAvatar>)>,
+    mut avatar: Query<(&mut Transform, &mut Visibility), (With<HeadsetAvatar>, Without<SpectatorCamera>)>,
+    mut camera: Query<(&mut Transform, &mut Projection), With<SpectatorCamera>>,
+) {
+    let head = match (root.single(), headset.single()) {
+        (Ok(root), Ok(headset)) => root.mul_transform(*headset),
+        _ => return,
+    };
+    if let Ok((mut transform, mut visibility)) = avatar.single_mut() {
+        *transform = head;
+        visibility.set_if_neq(if spectator.mode == SpectatorMode::ThirdPerson {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+    let Ok((mut transform, mut projection)) = camera.single_mut() else {
+        return;
+    };
+    let (target, fov) = match spectator.mode {
+        SpectatorMode::Off => return,
+        SpectatorMode::Mirror => (head, 90.0_f32),
+        SpectatorMode::ThirdPerson => {
+            // stay level, a tilting camera makes the audience sick
+            let back = (*head.back()).with_y(0.0).normalize_or(Vec3::Z);
+            let position = head.translation + back * FOLLOW_DISTANCE + Vec3::Y * FOLLOW_HEIGHT;
+            (
+                Transform::from_translation(position).looking_at(head.translation, Vec3::Y),
+                60.0,
+            )
+        }
+    };
+    let t = 1.0 - (-time.delta_secs() * SMOOTHING).exp();
+    transform.translation = transform.translation.lerp(target.translation, t);
+    transform.rotation = transform.rotation.slerp(target.rotation, t);
+    if let Projection::Perspective(perspective) = &mut *projection
+        && perspective.fov != fov.to_radians()
+    {
+        perspective.fov = fov.to_radians();
+    }
+}
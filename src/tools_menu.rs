@@ -0,0 +1,270 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_mod_xr::session::XrTrackingRoot;
+use schminput::prelude::*;
+
+use crate::{
+    capabilities::XrCapabilities,
+    capture::Capture,
+    lighting::{ActiveLighting, LightingSettings, LIGHTING_PRESETS},
+    mixer::{AudioMixer, Bus},
+    model_stats::ModelStats,
+    panel::{panel_text, set_panel_visible, spawn_world_panel, WorldPanel},
+    perf_hud::PerfHud,
+    render_quality::{RenderQualitySettings, QUALITY_PROFILES},
+    room_lighting::RoomLighting,
+    skeleton::SkeletonView,
+    HeadsetView, MoveActions,
+};
+
+// holding the left menu button this long opens the tools menu, a short press toggles passthrough
+pub const LONG_PRESS: f32 = 0.6;
+// where the menu opens, relative to the head and a bit below the line of sight
+const MENU_OFFSET: Vec3 = Vec3::new(0.0, -0.15, -0.5);
+
+// settings without a controller button of their own
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tool {
+    Lighting,
+    SunPaused,
+    SunSpeed,
+    RoomLighting,
+    Mute,
+    MasterVolume,
+    MusicVolume,
+    EffectsVolume,
+    RenderQuality,
+    Screenshot,
+    Recording,
+    CaptureView,
+    PerfHud,
+    Skeleton,
+    JointAxes,
+    ModelStats,
+}
+
+const TOOLS: &[Tool] = &[
+    Tool::Lighting,
+    Tool::SunPaused,
+    Tool::SunSpeed,
+    Tool::RoomLighting,
+    Tool::Mute,
+    Tool::MasterVolume,
+    Tool::MusicVolume,
+    Tool::EffectsVolume,
+    Tool::RenderQuality,
+    Tool::Screenshot,
+    Tool::Recording,
+    Tool::CaptureView,
+    Tool::PerfHud,
+    Tool::Skeleton,
+    Tool::JointAxes,
+    Tool::ModelStats,
+];
+
+// the left thumbstick was pushed left (-1) or right (1) on a menu entry,
+// the module owning the setting reacts to it
+#[derive(Message, Clone, Copy, Debug)]
+pub struct ToolInput {
+    pub tool: Tool,
+    pub direction: i32,
+}
+
+// the systems reacting to ToolInput, they run after the menu wrote it
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ToolInputSystems;
+
+// the resources the entries show, read only
+#[derive(SystemParam)]
+pub struct ToolStates<'w> {
+    lighting: Res<'w, LightingSettings>,
+    active_lighting: Res<'w, ActiveLighting>,
+    room_lighting: Res<'w, RoomLighting>,
+    mixer: Res<'w, AudioMixer>,
+    render_quality: Res<'w, RenderQualitySettings>,
+    capture: Res<'w, Capture>,
+    perf_hud: Res<'w, PerfHud>,
+    skeleton: Res<'w, SkeletonView>,
+    model_stats: Res<'w, ModelStats>,
+}
+
+impl Tool {
+    fn label(&self, states: &ToolStates) -> String {
+        match self {
+            Tool::Lighting => format!("lighting: {}", LIGHTING_PRESETS[states.lighting.global].name),
+            Tool::SunPaused => format!("sun paused: {}", states.lighting.sun_paused),
+            Tool::SunSpeed => format!(
+                "sun speed: {:.2} rad/s",
+                states.lighting.sun_speed(states.active_lighting.0)
+            ),
+            Tool::RoomLighting => format!("room lighting: {}", states.room_lighting.enabled),
+            Tool::Mute => format!("mute: {}", states.mixer.muted),
+            Tool::MasterVolume => volume_label(&states.mixer, Bus::Master),
+            Tool::MusicVolume => volume_label(&states.mixer, Bus::Music),
+            Tool::EffectsVolume => volume_label(&states.mixer, Bus::Effects),
+            Tool::RenderQuality => format!(
+                "render quality: {}",
+                QUALITY_PROFILES[states.render_quality.profile].name
+            ),
+            Tool::Screenshot => "take screenshot".to_string(),
+            Tool::Recording => format!("recording: {}", states.capture.recording.is_some()),
+            Tool::CaptureView => format!("capture view: {}", states.capture.view.name()),
+            Tool::PerfHud => format!("performance hud: {}", states.perf_hud.visible),
+            Tool::Skeleton => format!("skeleton: {}", states.skeleton.visible),
+            Tool::JointAxes => format!("joint axes: {}", states.skeleton.axes.name()),
+            Tool::ModelStats => format!("model statistics: {}", states.model_stats.visible),
+        }
+    }
+}
+
+fn volume_label(mixer: &AudioMixer, bus: Bus) -> String {
+    format!("{} volume: {:.0}%", bus.name(), mixer.volume(bus) * 100.0)
+}
+
+#[derive(Resource)]
+pub struct ToolsMenu {
+    pub open: bool,
+    selected: usize,
+    toggle_action: Entity,
+    // seconds the menu button is held, the menu toggles once per press
+    held: f32,
+    // like TurnState, one step per thumbstick movement
+    navigate_ready: bool,
+    panel: Option<WorldPanel>,
+    lines: Vec<Entity>,
+}
+
+pub fn setup_tools_menu(mut commands: Commands, capabilities: Res<XrCapabilities>) {
+    let set = commands.spawn(ActionSet::new("tools_menu", "Tools Menu", 1)).id();
+    let toggle_action = commands
+        .spawn((
+            Action::new("toggle_tools_menu", "Toggle Tools Menu (hold)", set),
+            capabilities.bindings("/user/hand/left/input/menu/click"),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::Tab)),
+            BoolActionValue::new(),
+        ))
+        .id();
+    commands.insert_resource(ToolsMenu {
+        open: false,
+        selected: 0,
+        toggle_action,
+        held: 0.0,
+        navigate_ready: true,
+        panel: None,
+        lines: vec![],
+    });
+}
+
+pub fn toggle_tools_menu(
+    mut commands: Commands,
+    mut menu: ResMut<ToolsMenu>,
+    bool_value: Query<&BoolActionValue>,
+    time: Res<Time>,
+    root: Query<&Transform, (With<XrTrackingRoot>, Without<HeadsetView>)>,
+    headset: Query<&Transform, (With<HeadsetView>, Without<XrTrackingRoot>)>,
+    mut cameras: Query<&mut Camera>,
+    targets: Query<&UiTargetCamera>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !bool_value.get(menu.toggle_action).unwrap().any {
+        menu.bypass_change_detection().held = 0.0;
+        return;
+    }
+    let held = menu.held;
+    menu.held += time.delta_secs();
+    if held >= LONG_PRESS || menu.held < LONG_PRESS {
+        return;
+    }
+    menu.open = !menu.open;
+    info!("tools menu: {}", menu.open);
+    if menu.panel.is_none() {
+        let panel = spawn_world_panel(
+            &mut commands,
+            &mut images,
+            &mut meshes,
+            &mut materials,
+            UVec2::new(512, 640),
+            0.3,
+        );
+        commands.entity(panel.root).with_child(panel_text("Tools", 28.0));
+        let lines = TOOLS
+            .iter()
+            .map(|_| {
+                let line = commands.spawn(panel_text("", 22.0)).id();
+                commands.entity(panel.root).add_child(line);
+                line
+            })
+            .collect();
+        menu.lines = lines;
+        menu.panel = Some(panel);
+    } else if let Some(panel) = &menu.panel {
+        set_panel_visible(&mut commands, panel, &mut cameras, &targets, menu.open);
+    }
+    if !menu.open {
+        return;
+    }
+    // world locked where the player looks, the headset pose is relative to the tracking root
+    let head = match (root.single(), headset.single()) {
+        (Ok(root), Ok(headset)) => root.mul_transform(*headset),
+        _ => Transform::from_xyz(0.0, 1.5, 1.0),
+    };
+    if let Some(panel) = &menu.panel {
+        let (yaw, _, _) = head.rotation.to_euler(EulerRot::YXZ);
+        commands.entity(panel.quad).insert(
+            Transform::from_translation(head.transform_point(MENU_OFFSET))
+                .with_rotation(Quat::from_rotation_y(yaw)),
+        );
+    }
+}
+
+// left thumbstick: up/down selects an entry, left/right changes it
+pub fn navigate_tools_menu(
+    mut menu: ResMut<ToolsMenu>,
+    move_actions: Res<MoveActions>,
+    vec2_value: Query<&Vec2ActionValue>,
+    mut tool_input: MessageWriter<ToolInput>,
+) {
+    if !menu.open {
+        return;
+    }
+    let stick = vec2_value.get(move_actions.move_action).unwrap().any;
+    if menu.navigate_ready && stick.y.abs() > 0.8 {
+        let count = TOOLS.len();
+        menu.selected = if stick.y > 0.0 {
+            (menu.selected + count - 1) % count
+        } else {
+            (menu.selected + 1) % count
+        };
+        menu.navigate_ready = false;
+    } else if menu.navigate_ready && stick.x.abs() > 0.8 {
+        tool_input.write(ToolInput {
+            tool: TOOLS[menu.selected],
+            direction: stick.x.signum() as i32,
+        });
+        menu.navigate_ready = false;
+    }
+    if stick.length() < 0.2 {
+        menu.bypass_change_detection().navigate_ready = true;
+    }
+}
+
+pub fn update_tools_menu(
+    menu: Res<ToolsMenu>,
+    states: ToolStates,
+    mut texts: Query<(&mut Text, &mut TextColor)>,
+) {
+    if !menu.open {
+        return;
+    }
+    for (index, (tool, line)) in TOOLS.iter().zip(&menu.lines).enumerate() {
+        if let Ok((mut text, mut color)) = texts.get_mut(*line) {
+            text.0 = tool.label(&states);
+            color.0 = if index == menu.selected {
+                Color::srgb(1.0, 0.8, 0.2)
+            } else {
+                Color::WHITE
+            };
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{prelude::*, scene::SceneInstanceReady};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use schminput::prelude::*;
+
+use crate::{
+    asset_handler::MAX_ASSET_ELEMENTS,
+    capabilities::XrCapabilities,
+    haptics::{Hand, Haptics},
+    light_editor::LightEditor,
+    pointer::LaserPointers,
+    settings::UserSettings,
+    MoveActions,
+};
+
+// longer recordings are cut off
+const MAX_SECONDS: u32 = 30;
+// the marker floats this far above the surface it was attached to
+const MARKER_OFFSET: f32 = 0.01;
+
+// a recorded clip attached to the model, point the laser at it to play it
+#[derive(Component)]
+pub struct VoiceNote {
+    clip: Handle<AudioSource>,
+}
+
+struct NoteTarget {
+    model: Entity,
+    // the catalog entry shown while recording
+    scene: usize,
+    // in model space
+    point: Vec3,
+    normal: Vec3,
+}
+
+// the model is despawned when another scene is shown, so the notes are kept per catalog entry
+#[derive(Clone)]
+struct StoredNote {
+    clip: Handle<AudioSource>,
+    // in model space
+    point: Vec3,
+    normal: Vec3,
+}
+
+#[derive(Resource)]
+pub struct VoiceNotes {
+    record_action: Entity,
+    pressed: bool,
+    // a wav file from the assets that is used instead of the microphone, e.g. for tests
+    stand_in: Option<String>,
+    target: Option<NoteTarget>,
+    stored: Vec<Vec<StoredNote>>,
+    marker_mesh: Handle<Mesh>,
+    marker_material: Handle<StandardMaterial>,
+}
+
+impl VoiceNotes {
+    fn attach(&self, commands: &mut Commands, model: Entity, note: &StoredNote) {
+        let marker = commands
+            .spawn((
+                VoiceNote {
+                    clip: note.clip.clone(),
+                },
+                Mesh3d(self.marker_mesh.clone()),
+                MeshMaterial3d(self.marker_material.clone()),
+                Transform::from_translation(note.point + note.normal * MARKER_OFFSET),
+            ))
+            .id();
+        commands.entity(model).add_child(marker);
+    }
+}
+
+// the cpal stream can't be sent between threads, so this is a non send resource
+#[derive(Default)]
+pub struct Recorder {
+    stream: Option<cpal::Stream>,
+    samples: Arc<Mutex<Vec<f32>>>,
+    sample_rate: u32,
+}
+
+impl Recorder {
+    fn start(&mut self) -> Result<(), String> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or("no input device")?;
+        let config = device.default_input_config().map_err(|err| err.to_string())?;
+        let channels = config.channels() as usize;
+        let sample_rate = config.sample_rate().0;
+        let max_samples = (sample_rate * MAX_SECONDS) as usize;
+        self.sample_rate = sample_rate;
+        self.samples.lock().unwrap().clear();
+        let samples = self.samples.clone();
+        let on_error = |err: cpal::StreamError| error!("voice note recording failed: {}", err);
+        let stream = match config.sample_format() {
+            cpal::SampleFormat::F32 => device.build_input_stream(
+                &config.into(),
+                move |data: &[f32], _: &_| push_mono(&samples, data, channels, max_samples),
+                on_error,
+                None,
+            ),
+            cpal::SampleFormat::I16 => device.build_input_stream(
+                &config.into(),
+                move |data: &[i16], _: &_| {
+                    let data: Vec<f32> =
+                        data.iter().map(|sample| *sample as f32 / i16::MAX as f32).collect();
+                    push_mono(&samples, &data, channels, max_samples);
+                },
+                on_error,
+                None,
+            ),
+            format => return Err(format!("unsupported sample format {:?}", format)),
+        }
+        .map_err(|err| err.to_string())?;
+        stream.play().map_err(|err| err.to_string())?;
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    // stops the stream and returns the recording as wav file
+    fn stop(&mut self) -> Option<Vec<u8>> {
+        self.stream.take()?;
+        let samples = std::mem::take(&mut *self.samples.lock().unwrap());
+        Some(wav_bytes(&samples, self.sample_rate))
+    }
+}
+
+// mixes the interleaved input down to one channel
+fn push_mono(samples: &Mutex<Vec<f32>>, data: &[f32], channels: usize, max_samples: usize) {
+    let mono = data
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32);
+    let mut samples = samples.lock().unwrap();
+    let room = max_samples.saturating_sub(samples.len());
+    samples.extend(mono.take(room));
+}
+
+// 16 bit mono pcm
+fn wav_bytes(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    let data_len = samples.len() as u32 * 2;
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    // pcm, one channel
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    bytes
+}
+
+pub fn setup_voice_notes(
+    mut commands: Commands,
+    capabilities: Res<XrCapabilities>,
+    settings: Res<UserSettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let set = commands.spawn(ActionSet::new("voice_notes", "Voice Notes", 1)).id();
+    let record_action = commands
+        .spawn((
+            Action::new("voice_note", "Record Or Play Voice Note", set),
+            capabilities.bindings("/user/hand/left/input/trigger/value"),
+            KeyboardBindings::new().bind(KeyboardBinding::new(KeyCode::KeyN)),
+            BoolActionValue::new(),
+        ))
+        .id();
+    commands.insert_resource(VoiceNotes {
+        record_action,
+        pressed: false,
+        stand_in: settings.get("voice_notes.stand_in"),
+        target: None,
+        stored: vec![vec![]; MAX_ASSET_ELEMENTS],
+        marker_mesh: meshes.add(Sphere::new(0.015)),
+        marker_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(1.0, 0.5, 0.1),
+            unlit: true,
+            ..default()
+        }),
+    });
+}
+
+// left trigger while pointing at the model: hold to record a note at the laser point,
+// left trigger while pointing at a note: play it
+pub fn record_voice_note(
+    mut commands: Commands,
+    mut notes: ResMut<VoiceNotes>,
+    mut recorder: NonSendMut<Recorder>,
+    editor: Res<LightEditor>,
+    lasers: Res<LaserPointers>,
+    move_actions: Res<MoveActions>,
+    bool_value: Query<&BoolActionValue>,
+    models: Query<(Entity, &GlobalTransform), With<SceneRoot>>,
+    voice_notes: Query<(Entity, &VoiceNote)>,
+    parents: Query<&ChildOf>,
+    asset_server: Res<AssetServer>,
+    mut clips: ResMut<Assets<AudioSource>>,
+    mut haptics: MessageWriter<Haptics>,
+) {
+    // the light editor places point lights with the left trigger
+    let pressed = !editor.active && bool_value.get(notes.record_action).unwrap().any;
+    let just_pressed = pressed && !notes.pressed;
+    let just_released = !pressed && notes.pressed;
+    notes.pressed = pressed;
+
+    if just_pressed {
+        let Ok((model, model_transform)) = models.single() else {
+            return;
+        };
+        let hit = lasers.hit(Hand::Left);
+        if let Some((note, voice_note)) = hit.and_then(|hit| voice_notes.get(hit.entity).ok()) {
+            commands.entity(note).with_child((
+                AudioPlayer::new(voice_note.clip.clone()),
+                PlaybackSettings::DESPAWN.with_spatial(true),
+                Transform::default(),
+            ));
+            return;
+        }
+        // without a laser hit, e.g. on the desktop, the note is placed above the model origin
+        let (point, normal) = hit
+            .filter(|hit| {
+                hit.entity == model || parents.iter_ancestors(hit.entity).any(|parent| parent == model)
+            })
+            .map_or((Vec3::Y, Vec3::Z), |hit| {
+                let to_model = model_transform.affine().inverse();
+                (
+                    to_model.transform_point3(hit.point),
+                    to_model.transform_vector3(hit.normal).normalize_or(Vec3::Z),
+                )
+            });
+        notes.target = Some(NoteTarget {
+            model,
+            scene: move_actions.shown_scene,
+            point,
+            normal,
+        });
+        if notes.stand_in.is_none()
+            && let Err(err) = recorder.start()
+        {
+            warn!("unable to record a voice note: {}", err);
+            notes.target = None;
+            return;
+        }
+        info!("recording voice note");
+        haptics.write(Haptics::tick(Hand::Left));
+    }
+
+    if !just_released {
+        return;
+    }
+    let Some(target) = notes.target.take() else {
+        return;
+    };
+    // the scene was switched while recording
+    if models.get(target.model).is_err() {
+        recorder.stop();
+        return;
+    }
+    let clip = match &notes.stand_in {
+        Some(path) => asset_server.load(path.clone()),
+        None => match recorder.stop() {
+            Some(bytes) => clips.add(AudioSource {
+                bytes: bytes.into(),
+            }),
+            None => return,
+        },
+    };
+    info!("voice note attached at {:?}", target.point);
+    haptics.write(Haptics::tick(Hand::Left));
+    let note = StoredNote {
+        clip,
+        point: target.point,
+        normal: target.normal,
+    };
+    notes.attach(&mut commands, target.model, &note);
+    if let Some(stored) = notes.stored.get_mut(target.scene) {
+        stored.push(note);
+    }
+}
+
+// the model is spawned again when its catalog entry is shown, its notes are attached again
+pub fn restore_voice_notes(
+    trigger: On<SceneInstanceReady>,
+    mut commands: Commands,
+    notes: Res<VoiceNotes>,
+    move_actions: Res<MoveActions>,
+    models: Query<(), With<SceneRoot>>,
+) {
+    if models.get(trigger.entity).is_err() {
+        return;
+    }
+    for note in notes.stored.get(move_actions.shown_scene).into_iter().flatten() {
+        notes.attach(&mut commands, trigger.entity, note);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::audio::{Decodable, Source};
+
+    use super::*;
+
+    #[test]
+    fn mixes_interleaved_input_to_mono() {
+        let samples = Mutex::new(vec![]);
+        push_mono(&samples, &[0.5, 0.1, -0.5, 0.3, 1.0, 1.0], 2, 2);
+        let samples = samples.lock().unwrap();
+        assert_eq!(samples.len(), 2);
+        assert!((samples[0] - 0.3).abs() < 1e-6);
+        assert!((samples[1] + 0.1).abs() < 1e-6);
+    }
+
+    // a recording written to disk plays like a stand-in wav from the assets
+    #[test]
+    fn wav_round_trip() {
+        let samples = [0.0, 0.5, -0.5, 1.0, -1.0, 2.0];
+        let path = std::env::temp_dir().join("voice_note_round_trip.wav");
+        std::fs::write(&path, wav_bytes(&samples, 22050)).unwrap();
+        let clip = AudioSource {
+            bytes: std::fs::read(&path).unwrap().into(),
+        };
+        std::fs::remove_file(&path).ok();
+        let decoder = clip.decoder();
+        assert_eq!(decoder.channels(), 1);
+        assert_eq!(decoder.sample_rate(), 22050);
+        let decoded: Vec<i16> = decoder.collect();
+        let expected: Vec<i16> = samples
+            .iter()
+            .map(|sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+            .collect();
+        assert_eq!(decoded, expected);
+    }
+}